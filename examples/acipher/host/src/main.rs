@@ -65,8 +65,8 @@ fn main() -> optee_teec::Result<()> {
         key_size = 256;
     }
 
-    let mut ctx = Context::new()?;
-    let uuid = Uuid::parse_str(UUID).unwrap();
+    let ctx = Context::new()?;
+    let uuid = Uuid::from(UUID);
     let mut session = ctx.open_session(uuid)?;
 
     gen_key(&mut session, key_size)?;
@@ -1,3 +1,5 @@
+use optee_uuid::{uuid, Uuid};
+
 pub enum Command {
     GenKey,
     GetSize,
@@ -19,4 +21,4 @@ impl From<u32> for Command {
     }
 }
 
-pub const UUID: &str = &include_str!(concat!(env!("OUT_DIR"), "/uuid.txt"));
+pub const UUID: Uuid = uuid!(include_str!(concat!(env!("OUT_DIR"), "/uuid.txt")));
@@ -58,8 +58,8 @@ fn cipher_buffer(
 }
 
 fn main() -> optee_teec::Result<()> {
-    let mut ctx = Context::new()?;
-    let uuid = Uuid::parse_str(UUID).unwrap();
+    let ctx = Context::new()?;
+    let uuid = Uuid::from(UUID);
     let mut session = ctx.open_session(uuid)?;
 
     let key = [0xa5u8; AES_TEST_KEY_SIZE];
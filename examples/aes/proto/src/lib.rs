@@ -1,3 +1,5 @@
+use optee_uuid::{uuid, Uuid};
+
 pub enum Command {
     Prepare,
     SetKey,
@@ -72,4 +74,4 @@ impl From<u32> for KeySize {
     }
 }
 
-pub const UUID: &str = &include_str!(concat!(env!("OUT_DIR"), "/uuid.txt"));
+pub const UUID: Uuid = uuid!(include_str!(concat!(env!("OUT_DIR"), "/uuid.txt")));
@@ -1,13 +1,11 @@
 #![no_main]
 
-use optee_utee::{
-    ta_close_session, ta_create, ta_destroy, ta_invoke_command, ta_open_session, trace_println,
-};
+use optee_utee::{ta_main, trace_println};
 use optee_utee::{AlgorithmId, Cipher, OperationMode};
 use optee_utee::{AttributeId, AttributeMemref, TransientObject, TransientObjectType};
 use optee_utee::{Error, ErrorKind, Parameters, Result};
+use optee_utee::{TaSession, TrustedApplication};
 use proto::{Algo, Command, KeySize, Mode};
-use std::boxed::Box;
 
 pub struct AesCipher {
     pub key_size: usize,
@@ -25,50 +23,45 @@ impl Default for AesCipher {
     }
 }
 
-#[ta_create]
-fn create() -> Result<()> {
-    trace_println!("[+] TA create");
-    Ok(())
-}
+struct AesTa;
 
-#[ta_open_session]
-fn open_session(_params: &mut Parameters, _sess_ctx: &mut AesCipher) -> Result<()> {
-    trace_println!("[+] TA open session");
-    Ok(())
-}
+impl TrustedApplication for AesTa {
+    type Session = AesCipher;
 
-#[ta_close_session]
-fn close_session(_sess_ctx: &mut AesCipher) {
-    trace_println!("[+] TA close session");
-}
+    fn create() -> Result<()> {
+        trace_println!("[+] TA create");
+        Ok(())
+    }
 
-#[ta_destroy]
-fn destroy() {
-    trace_println!("[+] TA destory");
+    fn destroy() {
+        trace_println!("[+] TA destory");
+    }
 }
 
-#[ta_invoke_command]
-fn invoke_command(sess_ctx: &mut AesCipher, cmd_id: u32, params: &mut Parameters) -> Result<()> {
-    trace_println!("[+] TA invoke command");
-    match Command::from(cmd_id) {
-        Command::Prepare => {
-            return alloc_resources(sess_ctx, params);
-        }
-        Command::SetKey => {
-            return set_aes_key(sess_ctx, params);
-        }
-        Command::SetIV => {
-            return reset_aes_iv(sess_ctx, params);
-        }
-        Command::Cipher => {
-            return cipher_buffer(sess_ctx, params);
-        }
-        _ => {
-            return Err(Error::new(ErrorKind::BadParameters));
+impl TaSession for AesCipher {
+    fn open(_params: &mut Parameters) -> Result<Self> {
+        trace_println!("[+] TA open session");
+        Ok(Self::default())
+    }
+
+    fn invoke(&mut self, cmd_id: u32, params: &mut Parameters) -> Result<()> {
+        trace_println!("[+] TA invoke command");
+        match Command::from(cmd_id) {
+            Command::Prepare => alloc_resources(self, params),
+            Command::SetKey => set_aes_key(self, params),
+            Command::SetIV => reset_aes_iv(self, params),
+            Command::Cipher => cipher_buffer(self, params),
+            _ => Err(Error::new(ErrorKind::BadParameters)),
         }
     }
+
+    fn close(self) {
+        trace_println!("[+] TA close session");
+    }
 }
 
+ta_main!(AesTa);
+
 pub fn ta2tee_algo_id(algo_id: u32) -> Result<AlgorithmId> {
     match Algo::from(algo_id) {
         Algo::ECB => Ok(AlgorithmId::AesEcbNopad),
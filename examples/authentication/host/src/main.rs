@@ -61,8 +61,8 @@ fn decrypt_final(
 }
 
 fn main() -> optee_teec::Result<()> {
-    let mut ctx = Context::new()?;
-    let uuid = Uuid::parse_str(UUID).unwrap();
+    let ctx = Context::new()?;
+    let uuid = Uuid::from(UUID);
     let mut session = ctx.open_session(uuid)?;
 
     let key = [0xa5u8; KEY_SIZE];
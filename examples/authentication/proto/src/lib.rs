@@ -1,3 +1,5 @@
+use optee_uuid::{uuid, Uuid};
+
 pub enum Command {
     Prepare,
     Update,
@@ -41,4 +43,4 @@ pub const KEY_SIZE: usize = 16;
 pub const AAD_LEN: usize = 16;
 pub const TAG_LEN: usize = 16;
 
-pub const UUID: &str = &include_str!(concat!(env!("OUT_DIR"), "/uuid.txt"));
+pub const UUID: Uuid = uuid!(include_str!(concat!(env!("OUT_DIR"), "/uuid.txt")));
@@ -19,13 +19,14 @@ fn big_int(session: &mut Session) -> optee_teec::Result<()> {
     session.invoke_command(Command::Multiply as u32, &mut operation)?;
     session.invoke_command(Command::Divide as u32, &mut operation)?;
     session.invoke_command(Command::Module as u32, &mut operation)?;
+    session.invoke_command(Command::Montgomery as u32, &mut operation)?;
 
     Ok(())
 }
 
 fn main() -> optee_teec::Result<()> {
-    let mut ctx = Context::new()?;
-    let uuid = Uuid::parse_str(UUID).unwrap();
+    let ctx = Context::new()?;
+    let uuid = Uuid::from(UUID);
     let mut session = ctx.open_session(uuid)?;
 
     big_int(&mut session)?;
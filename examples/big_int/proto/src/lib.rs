@@ -1,3 +1,5 @@
+use optee_uuid::{uuid, Uuid};
+
 pub enum Command {
     Compare,
     Convert,
@@ -6,6 +8,7 @@ pub enum Command {
     Multiply,
     Divide,
     Module,
+    Montgomery,
     Unknown,
 }
 
@@ -20,9 +23,10 @@ impl From<u32> for Command {
             4 => Command::Multiply,
             5 => Command::Divide,
             6 => Command::Module,
+            7 => Command::Montgomery,
             _ => Command::Unknown,
         }
     }
 }
 
-pub const UUID: &str = &include_str!(concat!(env!("OUT_DIR"), "/uuid.txt"));
+pub const UUID: Uuid = uuid!(include_str!(concat!(env!("OUT_DIR"), "/uuid.txt")));
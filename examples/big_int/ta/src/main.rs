@@ -1,11 +1,16 @@
 #![no_main]
 
-use optee_utee::BigInt;
+use optee_utee::heap::{self, TeeAllocator};
 use optee_utee::{
     ta_close_session, ta_create, ta_destroy, ta_invoke_command, ta_open_session, trace_println,
 };
+use optee_utee::{BigInt, Montgomery};
 use optee_utee::{Error, ErrorKind, Parameters, Result};
 use proto::Command;
+use std::convert::TryFrom;
+
+#[global_allocator]
+static ALLOCATOR: TeeAllocator = TeeAllocator::new();
 
 #[ta_create]
 fn create() -> Result<()> {
@@ -21,6 +26,13 @@ fn open_session(_params: &mut Parameters) -> Result<()> {
 
 #[ta_close_session]
 fn close_session() {
+    let stats = heap::heap_stats();
+    trace_println!(
+        "[+] TA heap: current {} bytes, peak {} bytes, {} failed allocations",
+        stats.current,
+        stats.peak,
+        stats.failed
+    );
     trace_println!("[+] TA close session");
 }
 
@@ -30,10 +42,12 @@ fn destroy() {
 }
 
 fn compare(n0: &BigInt, n1: &BigInt) -> Result<()> {
-    match n0.compare_big_int(n1) {
-        0 => trace_println!("{} == {}.", n0, n1),
-        res if res > 0 => trace_println!("{} > {}.", n0, n1),
-        _ => trace_println!("{} < {}.", n0, n1),
+    if n0 == n1 {
+        trace_println!("{} == {}.", n0, n1);
+    } else if n0 > n1 {
+        trace_println!("{} > {}.", n0, n1);
+    } else {
+        trace_println!("{} < {}.", n0, n1);
     }
     Ok(())
 }
@@ -44,30 +58,32 @@ fn convert(n0: &BigInt, n1: &BigInt) -> Result<()> {
         n0,
         n0.convert_to_octet_string().unwrap()
     );
-    trace_println!("{} in i32 is {}.", n1, n1.convert_to_s32().unwrap());
+    trace_println!("{} in i32 is {}.", n1, i32::try_from(n1)?);
+    trace_println!("{} in hex is {:#x}.", n0, n0);
     Ok(())
 }
 
 fn add(n0: &BigInt, n1: &BigInt) -> Result<()> {
-    let res = BigInt::add(n0, n1);
+    let res = n0 + n1;
     trace_println!("{} + {} = {}.", n0, n1, res);
     Ok(())
 }
 
 fn sub(n0: &BigInt, n1: &BigInt) -> Result<()> {
-    let res = BigInt::sub(n0, n1);
+    let res = n0 - n1;
     trace_println!("{} - {} = {}.", n0, n1, res);
     Ok(())
 }
 
 fn multiply(n0: &BigInt, n1: &BigInt) -> Result<()> {
-    let res = BigInt::multiply(n0, n1);
+    let res = n0 * n1;
     trace_println!("{} * {} = {}.", n0, n1, res);
     Ok(())
 }
 
 fn divide(n0: &BigInt, n1: &BigInt) -> Result<()> {
-    let (quot, rem) = BigInt::divide(n0, n1);
+    let quot = n0 / n1;
+    let rem = n0 % n1;
     trace_println!("{} / {} = {}, ramians {}.", n0, n1, quot, rem);
     Ok(())
 }
@@ -78,6 +94,49 @@ fn module(n0: &BigInt, n1: &BigInt) -> Result<()> {
     Ok(())
 }
 
+fn montgomery(n0: &BigInt, n1: &BigInt) -> Result<()> {
+    let mont = Montgomery::new(n0)?;
+    let x = mont.to_fmm(n1);
+    trace_println!(
+        "{} round trips through FMM mod {} as {}.",
+        n1,
+        n0,
+        mont.from_fmm(&x)
+    );
+    let y = n0 - n1;
+    trace_println!(
+        "{} round trips through FMM mod {} as {}.",
+        y,
+        n0,
+        mont.from_fmm(&mont.to_fmm(&y))
+    );
+    trace_println!(
+        "{} * {} mod {} = {}.",
+        n1,
+        n1,
+        n0,
+        mont.from_fmm(&mont.mul(&x, &x))
+    );
+    trace_println!(
+        "{} ^ 2 mod {} = {}.",
+        y,
+        n0,
+        mont.from_fmm(&mont.square(&mont.to_fmm(&y)))
+    );
+    let exp = BigInt::from(100);
+    let res = mont.pow(&x, &exp)?;
+    trace_println!(
+        "pow: {} ^ {} mod {} = {}.",
+        n1,
+        exp,
+        n0,
+        mont.from_fmm(&res)
+    );
+    let res = BigInt::exp_mod(n1, &exp, n0)?;
+    trace_println!("exp_mod: {} ^ {} mod {} = {}.", n1, exp, n0, res);
+    Ok(())
+}
+
 #[ta_invoke_command]
 fn invoke_command(cmd_id: u32, params: &mut Parameters) -> Result<()> {
     trace_println!("[+] TA invoke command");
@@ -98,6 +157,7 @@ fn invoke_command(cmd_id: u32, params: &mut Parameters) -> Result<()> {
         Command::Multiply => multiply(&n0, &n1),
         Command::Divide => divide(&n0, &n1),
         Command::Module => module(&n0, &n1),
+        Command::Montgomery => montgomery(&n0, &n1),
         _ => Err(Error::new(ErrorKind::BadParameters)),
     }
 }
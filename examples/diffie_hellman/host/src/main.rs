@@ -43,8 +43,8 @@ fn derive_key(key0_pub: &Vec<u8>, session: &mut Session) -> Result<()> {
 }
 
 fn main() -> Result<()> {
-    let mut ctx = Context::new()?;
-    let uuid = Uuid::parse_str(UUID).unwrap();
+    let ctx = Context::new()?;
+    let uuid = Uuid::from(UUID);
     let mut session = ctx.open_session(uuid)?;
 
     let (mut key0_public, key0_private) = generate_key(&mut session).unwrap();
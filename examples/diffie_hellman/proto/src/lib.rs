@@ -1,3 +1,5 @@
+use optee_uuid::{uuid, Uuid};
+
 pub enum Command {
     GenerateKey,
     DeriveKey,
@@ -17,4 +19,4 @@ impl From<u32> for Command {
 
 pub const KEY_SIZE: usize = 256;
 
-pub const UUID: &str = &include_str!(concat!(env!("OUT_DIR"), "/uuid.txt"));
+pub const UUID: Uuid = uuid!(include_str!(concat!(env!("OUT_DIR"), "/uuid.txt")));
@@ -33,8 +33,8 @@ fn main() -> optee_teec::Result<()> {
         return Err(Error::new(ErrorKind::BadParameters));
     }
 
-    let mut ctx = Context::new()?;
-    let uuid = Uuid::parse_str(UUID).unwrap();
+    let ctx = Context::new()?;
+    let uuid = Uuid::from(UUID);
 
     let mut hash: [u8; 32] = [0u8; 32];
     let mut session = ctx.open_session(uuid)?;
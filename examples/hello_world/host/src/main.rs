@@ -1,6 +1,6 @@
 use optee_teec::{Context, Operation, ParamType, Session, Uuid};
 use optee_teec::{ParamNone, ParamValue};
-use proto::{UUID, Command};
+use proto::{UUID, Command, HelloWorldError};
 
 fn hello_world(session: &mut Session) -> optee_teec::Result<()> {
     let p0 = ParamValue::new(29, 0, ParamType::ValueInout);
@@ -13,12 +13,30 @@ fn hello_world(session: &mut Session) -> optee_teec::Result<()> {
 
     session.invoke_command(Command::DecValue as u32, &mut operation)?;
     println!("dec value is {:?}", operation.parameters().0.a());
+
+    match session.invoke_command(Command::DecValue as u32, &mut operation) {
+        Err(e) if e.custom() == Some(HelloWorldError::ValueUnderflow) => {
+            println!("dec value is rejected: {:?}", HelloWorldError::ValueUnderflow)
+        }
+        res => res?,
+    }
+
+    for record in session.take_trace_records()? {
+        if let (Some(level), Some((file, line))) = (record.level, record.location) {
+            println!("TA {:?} {}:{}: {}", level, file, line, record.message);
+        }
+    }
     Ok(())
 }
 
 fn main() -> optee_teec::Result<()> {
-    let mut ctx = Context::new()?;
-    let uuid = Uuid::parse_str(UUID).unwrap();
+    if !Context::probe()? {
+        println!("No TEE found");
+        return Ok(());
+    }
+    println!("TEE is available");
+    let ctx = Context::new()?;
+    let uuid = Uuid::from(UUID);
     let mut session = ctx.open_session(uuid)?;
 
     hello_world(&mut session)?;
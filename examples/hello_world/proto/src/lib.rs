@@ -1,3 +1,6 @@
+use optee_error::CustomError;
+use optee_uuid::{uuid, Uuid};
+
 pub enum Command {
     IncValue,
     DecValue,
@@ -15,4 +18,9 @@ impl From<u32> for Command {
     }
 }
 
-pub const UUID: &str = &include_str!(concat!(env!("OUT_DIR"), "/uuid.txt"));
+#[derive(Clone, Copy, CustomError, Debug, PartialEq)]
+pub enum HelloWorldError {
+    ValueUnderflow,
+}
+
+pub const UUID: Uuid = uuid!(include_str!(concat!(env!("OUT_DIR"), "/uuid.txt")));
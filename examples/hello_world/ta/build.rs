@@ -1,9 +1,7 @@
-use proto;
 use std::env;
 use std::fs::File;
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use uuid::Uuid;
 
 fn main() -> std::io::Result<()> {
     let out = &PathBuf::from(env::var_os("OUT_DIR").unwrap());
@@ -11,19 +9,10 @@ fn main() -> std::io::Result<()> {
     let mut buffer = File::create(out.join("user_ta_header.rs"))?;
     buffer.write_all(include_bytes!("ta_static.rs"))?;
 
-    let tee_uuid = Uuid::parse_str(proto::UUID).unwrap();
-    let (time_low, time_mid, time_hi_and_version, clock_seq_and_node) = tee_uuid.as_fields();
-
     write!(buffer, "\n")?;
     write!(
         buffer,
-        "const TA_UUID: optee_utee_sys::TEE_UUID = optee_utee_sys::TEE_UUID {{
-    timeLow: {:#x},
-    timeMid: {:#x},
-    timeHiAndVersion: {:#x},
-    clockSeqAndNode: {:#x?},
-}};",
-        time_low, time_mid, time_hi_and_version, clock_seq_and_node
+        "const TA_UUID: optee_utee_sys::TEE_UUID = proto::UUID.to_tee_uuid();"
     )?;
     let optee_os_dir = env::var("OPTEE_OS_DIR").unwrap_or("../../../optee/optee_os".to_string());
     let search_path = match env::var("ARCH") {
@@ -1,15 +1,18 @@
 #![no_main]
 
 use optee_utee::{
-    ta_close_session, ta_create, ta_destroy, ta_invoke_command, ta_open_session, trace_println,
+    ta_close_session, ta_create, ta_destroy, ta_instance_state, ta_invoke_command, ta_open_session,
+    trace_debug, trace_println,
 };
 use optee_utee::{Error, ErrorKind, Parameters, Result};
-use proto::Command;
+use proto::{Command, HelloWorldError};
+
+ta_instance_state!(static INVOCATIONS: u32);
 
 #[ta_create]
 fn create() -> Result<()> {
     trace_println!("[+] TA create");
-    Ok(())
+    INVOCATIONS.init(0)
 }
 
 #[ta_open_session]
@@ -20,25 +23,35 @@ fn open_session(_params: &mut Parameters) -> Result<()> {
 
 #[ta_close_session]
 fn close_session() {
+    if let Ok(count) = INVOCATIONS.with(|count| *count) {
+        trace_println!("[+] TA commands invoked by this instance: {}", count);
+    }
     trace_println!("[+] TA close session");
 }
 
 #[ta_destroy]
 fn destroy() {
+    INVOCATIONS.destroy();
     trace_println!("[+] TA destroy");
 }
 
 #[ta_invoke_command]
 fn invoke_command(cmd_id: u32, params: &mut Parameters) -> Result<()> {
     trace_println!("[+] TA invoke command");
+    INVOCATIONS.with(|count| *count += 1)?;
     let mut values = unsafe { params.0.as_value().unwrap() };
     match Command::from(cmd_id) {
         Command::IncValue => {
             values.set_a(values.a() + 100);
+            trace_debug!("value incremented to {}", values.a());
             Ok(())
         }
         Command::DecValue => {
+            if values.a() < 100 {
+                return Err(HelloWorldError::ValueUnderflow.into());
+            }
             values.set_a(values.a() - 100);
+            trace_debug!("value decremented to {}", values.a());
             Ok(())
         }
         _ => Err(Error::new(ErrorKind::BadParameters)),
@@ -46,8 +46,8 @@ fn get_hotp(session: &mut Session) -> optee_teec::Result<()> {
 }
 
 fn main() -> optee_teec::Result<()> {
-    let mut ctx = Context::new()?;
-    let uuid = Uuid::parse_str(UUID).unwrap();
+    let ctx = Context::new()?;
+    let uuid = Uuid::from(UUID);
     let mut session = ctx.open_session(uuid)?;
 
     register_shared_key(&mut session)?;
@@ -1,11 +1,12 @@
 #![no_main]
 
+use optee_utee::mem::AccessFlags;
 use optee_utee::{
     ta_close_session, ta_create, ta_destroy, ta_invoke_command, ta_open_session, trace_println,
 };
 use optee_utee::{AlgorithmId, Mac};
 use optee_utee::{AttributeId, AttributeMemref, TransientObject, TransientObjectType};
-use optee_utee::{Error, ErrorKind, Parameters, Result};
+use optee_utee::{Error, ErrorKind, Parameters, Result, Secret};
 use proto::Command;
 
 pub const SHA1_HASH_SIZE: usize = 20;
@@ -15,7 +16,7 @@ pub const DBC2_MODULO: u32 = 1000000;
 
 pub struct HmacOtp {
     pub counter: [u8; 8],
-    pub key: [u8; MAX_KEY_SIZE],
+    pub key: Secret<[u8; MAX_KEY_SIZE]>,
     pub key_len: usize,
 }
 
@@ -23,7 +24,7 @@ impl Default for HmacOtp {
     fn default() -> Self {
         Self {
             counter: [0u8; 8],
-            key: [0u8; MAX_KEY_SIZE],
+            key: Secret::new([0u8; MAX_KEY_SIZE]),
             key_len: 0,
         }
     }
@@ -69,9 +70,10 @@ fn invoke_command(sess_ctx: &mut HmacOtp, cmd_id: u32, params: &mut Parameters)
 
 pub fn register_shared_key(hotp: &mut HmacOtp, params: &mut Parameters) -> Result<()> {
     let mut p = unsafe { params.0.as_memref().unwrap() };
+    p.check_access_rights(AccessFlags::READ | AccessFlags::ANY_OWNER)?;
     let buffer = p.buffer();
     hotp.key_len = buffer.len();
-    hotp.key[..hotp.key_len].clone_from_slice(buffer);
+    hotp.key.expose_mut()[..hotp.key_len].clone_from_slice(buffer);
     Ok(())
 }
 
@@ -104,9 +106,8 @@ pub fn hmac_sha1(hotp: &mut HmacOtp, out: &mut [u8]) -> Result<usize> {
                 Err(e) => return Err(e),
                 Ok(mut key_object) => {
                     //KEY size can be larger than hotp.key_len
-                    let mut tmp_key = hotp.key.to_vec();
-                    tmp_key.truncate(hotp.key_len);
-                    let attr = AttributeMemref::from_ref(AttributeId::SecretValue, &tmp_key);
+                    let key = &hotp.key.expose()[..hotp.key_len];
+                    let attr = AttributeMemref::from_ref(AttributeId::SecretValue, key);
                     key_object.populate(&[attr.into()])?;
                     mac.set_key(&key_object)?;
                 }
@@ -1,3 +1,4 @@
+use optee_uuid::{uuid, Uuid};
 use serde::{Serialize, Deserialize};
 pub use serde_json;
 
@@ -31,4 +32,4 @@ impl From<u32> for Command {
 }
 
 
-pub const UUID: &str = &include_str!(concat!(env!("OUT_DIR"), "/uuid.txt"));
+pub const UUID: Uuid = uuid!(include_str!(concat!(env!("OUT_DIR"), "/uuid.txt")));
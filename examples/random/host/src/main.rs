@@ -17,9 +17,9 @@ fn random(session: &mut Session) -> optee_teec::Result<()> {
 }
 
 fn main() -> optee_teec::Result<()> {
-    let mut ctx = Context::new()?;
+    let ctx = Context::new()?;
 
-    let uuid = Uuid::parse_str(UUID).unwrap();
+    let uuid = Uuid::from(UUID);
     let mut session = ctx.open_session(uuid)?;
 
     random(&mut session)?;
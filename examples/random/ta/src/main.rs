@@ -4,7 +4,7 @@ use optee_utee::{
     ta_close_session, ta_create, ta_destroy, ta_invoke_command, ta_open_session, trace_println,
 };
 use optee_utee::{Error, ErrorKind, Parameters, Result};
-use optee_utee::{Random};
+use optee_utee::{random, Random};
 use proto::Command;
 
 #[ta_create]
@@ -32,6 +32,7 @@ fn destroy() {
 pub fn random_number_generate(params: &mut Parameters) -> Result<()> {
     let mut p = unsafe { params.0.as_memref().unwrap()};
     Random::generate(p.buffer());
+    trace_println!("[+] TA rolled a dice: {}", random::random_range(1..7));
     Ok(())
 }
 
@@ -45,8 +45,8 @@ fn delete_secure_object(session: &mut Session, obj_id: &[u8]) -> optee_teec::Res
 }
 
 fn main() -> optee_teec::Result<()> {
-    let mut ctx = Context::new()?;
-    let uuid = Uuid::parse_str(UUID).unwrap();
+    let ctx = Context::new()?;
+    let uuid = Uuid::from(UUID);
     let mut session = ctx.open_session(uuid)?;
 
     let obj1_id = CString::new("object#1").unwrap().into_bytes_with_nul();
@@ -1,11 +1,12 @@
 #![no_main]
 
-use optee_utee::Time;
+use optee_utee::{Instant, Time};
 use optee_utee::{
     ta_close_session, ta_create, ta_destroy, ta_invoke_command, ta_open_session, trace_println,
 };
 use optee_utee::{Error, ErrorKind, Parameters, Result};
 use proto::Command;
+use std::time::Duration;
 
 #[ta_create]
 fn create() -> Result<()> {
@@ -46,13 +47,14 @@ fn time() -> Result<()> {
     time.ree_time();
     trace_println!("[+] Get REE time {}.", time);
     trace_println!("[+] Now wait 1 second in TEE ...");
-    Time::wait(1000)?;
+    let start = Instant::now();
+    Time::wait(Duration::from_secs(1))?;
+    trace_println!("[+] Waited {} ms.", start.elapsed().as_millis());
     time.system_time();
     trace_println!("[+] Get system time {}.", time);
     time.seconds = time.seconds + 5;
     time.set_ta_time()?;
-    let mut time2 = Time::new();
-    time2.ta_time()?;
+    let time2 = Time::persistent_time()?;
     trace_println!("[+] After set the TA time 5 seconds ahead of system time, new TA time {}.", time2);
     Ok(())
 }
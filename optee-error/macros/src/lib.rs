@@ -0,0 +1,98 @@
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::parse_macro_input;
+use syn::spanned::Spanned;
+
+/// Derive `optee_error::CustomError` for an enum without fields.
+///
+/// # Examples
+///
+/// ```ignore
+/// use optee_error::CustomError;
+///
+/// #[derive(CustomError)]
+/// pub enum WalletError {
+///     PinBlocked = 1,
+///     CounterExhausted = 2,
+/// }
+/// ```
+#[proc_macro_derive(CustomError)]
+pub fn derive_custom_error(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    let ident = &input.ident;
+
+    let data = match input.data {
+        syn::Data::Enum(ref data) => data,
+        _ => {
+            return syn::parse::Error::new(
+                input.span(),
+                "`#[derive(CustomError)]` can only be used on enums",
+            )
+            .to_compile_error()
+            .into();
+        }
+    };
+    for variant in data.variants.iter() {
+        match variant.fields {
+            syn::Fields::Unit => (),
+            _ => {
+                return syn::parse::Error::new(
+                    variant.span(),
+                    "`#[derive(CustomError)]` variants must not have fields",
+                )
+                .to_compile_error()
+                .into();
+            }
+        }
+    }
+
+    let paths: Vec<_> = data
+        .variants
+        .iter()
+        .map(|v| {
+            let variant = &v.ident;
+            quote!(#ident::#variant)
+        })
+        .collect();
+    // The codes start after `CUSTOM_ERROR_BASE`, which is the code of `ErrorKind::Custom`, and
+    // must fit in the reserved range.
+    let checks = paths
+        .iter()
+        .map(|path| quote!(const _: [(); 0] = [(); ((#path as u32 + 1) >> 28) as usize];));
+    let codes: Vec<_> = paths
+        .iter()
+        .map(|path| quote!(::optee_error::CUSTOM_ERROR_BASE + 1 + #path as u32))
+        .collect();
+    let match_arms = paths
+        .iter()
+        .zip(codes.iter())
+        .map(|(path, code)| quote!(#path => #code,));
+    let from_code = paths.iter().zip(codes.iter()).map(|(path, code)| {
+        quote!(
+            if code == #code {
+                return Some(#path);
+            }
+        )
+    });
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    quote!(
+        #(#checks)*
+
+        impl #impl_generics ::optee_error::CustomError for #ident #ty_generics #where_clause {
+            fn code(&self) -> u32 {
+                match *self {
+                    #(#match_arms)*
+                }
+            }
+
+            fn from_code(code: u32) -> Option<Self> {
+                #(#from_code)*
+                None
+            }
+        }
+    )
+    .into()
+}
@@ -0,0 +1,66 @@
+//! The mapping between `io::ErrorKind` and the GP-defined error codes, which
+//! are the same in the TEE client API and the TEE internal core API.
+
+use std::io;
+
+const ERROR_GENERIC: u32 = 0xFFFF_0000;
+const ERROR_ACCESS_DENIED: u32 = 0xFFFF_0001;
+const ERROR_CANCEL: u32 = 0xFFFF_0002;
+const ERROR_ACCESS_CONFLICT: u32 = 0xFFFF_0003;
+const ERROR_EXCESS_DATA: u32 = 0xFFFF_0004;
+const ERROR_BAD_FORMAT: u32 = 0xFFFF_0005;
+const ERROR_BAD_PARAMETERS: u32 = 0xFFFF_0006;
+const ERROR_ITEM_NOT_FOUND: u32 = 0xFFFF_0008;
+const ERROR_NO_DATA: u32 = 0xFFFF_000B;
+const ERROR_BUSY: u32 = 0xFFFF_000D;
+const ERROR_COMMUNICATION: u32 = 0xFFFF_000E;
+const ERROR_SECURITY: u32 = 0xFFFF_000F;
+const ERROR_SHORT_BUFFER: u32 = 0xFFFF_0010;
+const ERROR_EXTERNAL_CANCEL: u32 = 0xFFFF_0011;
+const ERROR_TARGET_DEAD: u32 = 0xFFFF_3024;
+const ERROR_MAC_INVALID: u32 = 0xFFFF_3071;
+const ERROR_SIGNATURE_INVALID: u32 = 0xFFFF_3072;
+const ERROR_CORRUPT_OBJECT: u32 = 0xF010_0001;
+const ERROR_CORRUPT_OBJECT_2: u32 = 0xF010_0002;
+
+/// Returns the error code closest to `kind`, `TEE_ERROR_GENERIC` if there is
+/// none.
+pub fn error_code(kind: io::ErrorKind) -> u32 {
+    match kind {
+        io::ErrorKind::NotFound => ERROR_ITEM_NOT_FOUND,
+        io::ErrorKind::PermissionDenied => ERROR_ACCESS_DENIED,
+        io::ErrorKind::AlreadyExists => ERROR_ACCESS_CONFLICT,
+        io::ErrorKind::InvalidInput => ERROR_BAD_PARAMETERS,
+        io::ErrorKind::InvalidData => ERROR_BAD_FORMAT,
+        io::ErrorKind::UnexpectedEof => ERROR_NO_DATA,
+        io::ErrorKind::WouldBlock => ERROR_BUSY,
+        io::ErrorKind::Interrupted => ERROR_CANCEL,
+        io::ErrorKind::BrokenPipe
+        | io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::ConnectionReset => ERROR_COMMUNICATION,
+        _ => ERROR_GENERIC,
+    }
+}
+
+/// Returns the `io::ErrorKind` closest to the error code `code`, `Other` if
+/// there is none.
+pub fn error_kind(code: u32) -> io::ErrorKind {
+    match code {
+        ERROR_ITEM_NOT_FOUND => io::ErrorKind::NotFound,
+        ERROR_ACCESS_DENIED | ERROR_SECURITY => io::ErrorKind::PermissionDenied,
+        ERROR_BAD_PARAMETERS | ERROR_EXCESS_DATA | ERROR_SHORT_BUFFER => {
+            io::ErrorKind::InvalidInput
+        }
+        ERROR_BAD_FORMAT
+        | ERROR_CORRUPT_OBJECT
+        | ERROR_CORRUPT_OBJECT_2
+        | ERROR_MAC_INVALID
+        | ERROR_SIGNATURE_INVALID => io::ErrorKind::InvalidData,
+        ERROR_NO_DATA => io::ErrorKind::UnexpectedEof,
+        ERROR_BUSY => io::ErrorKind::WouldBlock,
+        ERROR_CANCEL | ERROR_EXTERNAL_CANCEL => io::ErrorKind::Interrupted,
+        ERROR_COMMUNICATION => io::ErrorKind::BrokenPipe,
+        ERROR_TARGET_DEAD => io::ErrorKind::ConnectionAborted,
+        _ => io::ErrorKind::Other,
+    }
+}
@@ -0,0 +1,78 @@
+//! Application-defined error codes, which a trusted application returns in
+//! place of the GP-defined ones and the host turns back into the original
+//! error.
+//!
+//! The error type is declared in the proto crate and derives
+//! [CustomError](trait.CustomError.html):
+//!
+//! ```
+//! use optee_error::CustomError;
+//!
+//! #[derive(Clone, Copy, CustomError, Debug, PartialEq)]
+//! pub enum WalletError {
+//!     PinBlocked,
+//!     CounterExhausted,
+//! }
+//!
+//! let code = WalletError::PinBlocked.code();
+//! assert_eq!(WalletError::from_code(code), Some(WalletError::PinBlocked));
+//! ```
+//!
+//! The TA returns it through `optee_utee::Error`:
+//!
+//! ```ignore
+//! fn check_pin(&mut self, pin: &[u8]) -> optee_utee::Result<()> {
+//!     if self.attempts == 0 {
+//!         return Err(WalletError::PinBlocked.into());
+//!     }
+//!     // ...
+//! }
+//! ```
+//!
+//! and the host gets it back from `optee_teec::Error`:
+//!
+//! ```ignore
+//! match session.invoke_command(Command::Pay as u32, &mut operation) {
+//!     Err(e) if e.custom() == Some(WalletError::PinBlocked) => println!("PIN blocked"),
+//!     res => res?,
+//! }
+//! ```
+
+#![no_std]
+
+#[cfg(feature = "std")]
+extern crate std;
+
+pub use optee_error_macros::CustomError;
+
+#[cfg(feature = "std")]
+pub mod io;
+
+/// The first code of the range reserved for application-defined errors. The
+/// codes of the GP APIs and OP-TEE are all above the range.
+pub const CUSTOM_ERROR_BASE: u32 = 0x7000_0000;
+
+/// The last code of the range reserved for application-defined errors.
+pub const CUSTOM_ERROR_MAX: u32 = 0x7FFF_FFFF;
+
+/// An error type of a trusted application, converted from and to a code in
+/// the reserved range.
+///
+/// The derive macro implements the trait for enums without fields: the code
+/// of a variant is `CUSTOM_ERROR_BASE + 1` plus its discriminant, so the
+/// discriminants must stay below `0x0FFF_FFFF`, which is checked at compile
+/// time. `CUSTOM_ERROR_BASE` itself is the code of `ErrorKind::Custom`, an
+/// application-defined error of no particular type.
+pub trait CustomError: Sized {
+    /// Returns the code of the error.
+    fn code(&self) -> u32;
+
+    /// Returns the error with the code `code`, or `None` if the code does not
+    /// belong to this type.
+    fn from_code(code: u32) -> Option<Self>;
+}
+
+/// Returns `true` if `code` is in the range of application-defined errors.
+pub fn is_custom_error(code: u32) -> bool {
+    code >= CUSTOM_ERROR_BASE && code <= CUSTOM_ERROR_MAX
+}
@@ -0,0 +1,507 @@
+//! An asynchronous client API, enabled with the `async` feature.
+//!
+//! The calls to the TEE block the calling thread until the trusted application
+//! returns, so they run on a dedicated pool of worker threads and the caller
+//! gets a future, which can be awaited from any executor, e.g. tokio, without
+//! blocking it. Dropping the future before it completes cancels the command
+//! with `TEEC_RequestCancellation`.
+//!
+//! The pool runs at most 16 calls at the same time, the others wait for a
+//! worker. The timeouts of all the calls are handled by a single timer thread.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! let ctx = Context::new()?;
+//! let session = AsyncSession::open(&ctx, uuid).await?;
+//! let operation = Operation::new(0, ParamValue::new(29, 0, ParamType::ValueInout),
+//!                                ParamNone, ParamNone, ParamNone);
+//! let operation = session
+//!     .invoke_command(0, operation)
+//!     .timeout(Duration::from_secs(5))
+//!     .await?;
+//! ```
+
+use crate::{Context, Error, ErrorKind, Operation, Result, Session, Uuid};
+use crate::{Param, ParamNone};
+use optee_teec_sys as raw;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::ptr;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Once, Weak};
+use std::task::{Context as TaskContext, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Idle workers exit after this delay.
+const WORKER_KEEP_ALIVE: Duration = Duration::from_secs(60);
+/// The largest number of workers, the TEE only runs a few threads itself and
+/// the calls beyond would wait in the driver anyway.
+const MAX_WORKERS: usize = 16;
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// A session whose commands are invoked on the worker pool.
+///
+/// The handle can be cloned and shared between tasks, the commands of one
+/// session are still invoked one at a time.
+#[derive(Clone)]
+pub struct AsyncSession {
+    session: Arc<Mutex<Session<'static>>>,
+}
+
+impl AsyncSession {
+    /// Opens a session to the trusted application `uuid`.
+    ///
+    /// Opening a session cannot be cancelled: if the future is dropped, the
+    /// session is closed once it is opened.
+    pub fn open(context: &Context, uuid: Uuid) -> AsyncCall<AsyncSession> {
+        let context = context.inner().clone();
+        spawn(move || {
+            Session::open::<ParamNone, ParamNone, ParamNone, ParamNone>(context, uuid, None).map(
+                |session| AsyncSession {
+                    session: Arc::new(Mutex::new(session)),
+                },
+            )
+        })
+    }
+
+    /// Invokes a command and resolves to the operation, which holds the
+    /// output parameters.
+    ///
+    /// The parameters must own their content, e.g. values. Commands with
+    /// temporary memory references are invoked with [call](AsyncSession::call).
+    pub fn invoke_command<A, B, C, D>(
+        &self,
+        command_id: u32,
+        mut operation: Operation<A, B, C, D>,
+    ) -> AsyncCall<Operation<A, B, C, D>>
+    where
+        A: Param + Send + 'static,
+        B: Param + Send + 'static,
+        C: Param + Send + 'static,
+        D: Param + Send + 'static,
+    {
+        self.call(move |session| {
+            session.invoke_command(command_id, &mut operation)?;
+            Ok(operation)
+        })
+    }
+
+    /// Runs `f` with the session on a worker thread and resolves to its
+    /// result.
+    ///
+    /// The commands invoked by `f` are cancelled with the future: a command
+    /// which has not started yet returns a `Cancel` error without reaching the
+    /// trusted application.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let digest = session
+    ///     .call(move |session| {
+    ///         let p0 = ParamTmpRef::new_input(&message);
+    ///         let p1 = ParamTmpRef::new_output(&mut digest);
+    ///         let mut operation = Operation::new(0, p0, p1, ParamNone, ParamNone);
+    ///         session.invoke_command(Command::Hash as u32, &mut operation)?;
+    ///         Ok(digest)
+    ///     })
+    ///     .await?;
+    /// ```
+    pub fn call<T, F>(&self, f: F) -> AsyncCall<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Session) -> Result<T> + Send + 'static,
+    {
+        let session = self.session.clone();
+        spawn(move || f(&mut lock(&session)))
+    }
+}
+
+/// A call running on the worker pool, which resolves to its result.
+///
+/// Dropping the future before it completes cancels the call.
+#[must_use = "futures do nothing unless polled, dropping the call cancels it"]
+pub struct AsyncCall<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T: Send + 'static> AsyncCall<T> {
+    /// Cancels the call if it is not completed after `duration`, the future
+    /// then resolves to a `Cancel` error.
+    ///
+    /// A trusted application which ignores the cancellation keeps its worker
+    /// busy until the command returns, but the future does not wait for it.
+    pub fn timeout(self, duration: Duration) -> AsyncCall<T> {
+        // The timer does not keep a completed call alive until the deadline.
+        let shared = Arc::downgrade(&self.shared);
+        Timer::get().schedule(
+            Instant::now() + duration,
+            Box::new(move || {
+                if let Some(shared) = Weak::upgrade(&shared) {
+                    let state = lock(&shared.state);
+                    if !state.finished {
+                        shared.complete(state, Ok(Err(Error::new(ErrorKind::Cancel))));
+                        shared.cancel.request();
+                    }
+                }
+            }),
+        );
+        self
+    }
+}
+
+impl<T> AsyncCall<T> {
+    /// Returns a handle which cancels the call, e.g. from another task.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        CancelHandle {
+            cancel: self.shared.cancel.clone(),
+        }
+    }
+}
+
+impl<T> Future for AsyncCall<T> {
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext) -> Poll<Result<T>> {
+        let mut state = lock(&self.shared.state);
+        match state.result.take() {
+            Some(Ok(result)) => Poll::Ready(result),
+            Some(Err(payload)) => {
+                drop(state);
+                panic::resume_unwind(payload)
+            }
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Drop for AsyncCall<T> {
+    fn drop(&mut self) {
+        let mut state = lock(&self.shared.state);
+        if !state.finished {
+            state.finished = true;
+            drop(state);
+            self.shared.cancel.request();
+        }
+    }
+}
+
+/// Cancels an [AsyncCall](AsyncCall), which then resolves to the result
+/// returned by the trusted application, usually a `Cancel` error.
+#[derive(Clone)]
+pub struct CancelHandle {
+    cancel: Arc<Cancel>,
+}
+
+impl CancelHandle {
+    /// Requests the cancellation of the call.
+    pub fn cancel(&self) {
+        self.cancel.request();
+    }
+}
+
+struct State<T> {
+    finished: bool,
+    result: Option<thread::Result<Result<T>>>,
+    waker: Option<Waker>,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    cancel: Arc<Cancel>,
+}
+
+impl<T> Shared<T> {
+    /// Stores the result unless the call already completed, e.g. timed out.
+    fn complete(&self, mut state: MutexGuard<State<T>>, result: thread::Result<Result<T>>) {
+        if state.finished {
+            return;
+        }
+        state.finished = true;
+        state.result = Some(result);
+        let waker = state.waker.take();
+        drop(state);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+struct CancelState {
+    requested: bool,
+    operation: *mut raw::TEEC_Operation,
+}
+
+/// The cancellation state of a call, shared with the worker running it.
+struct Cancel {
+    state: Mutex<CancelState>,
+}
+
+// The operation pointer is only used while the worker holds its registration.
+unsafe impl Send for Cancel {}
+unsafe impl Sync for Cancel {}
+
+impl Cancel {
+    fn request(&self) {
+        let mut state = lock(&self.state);
+        state.requested = true;
+        if !state.operation.is_null() {
+            // The lock keeps the worker from releasing the operation meanwhile.
+            unsafe { raw::TEEC_RequestCancellation(state.operation) };
+        }
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Arc<Cancel>>> = RefCell::new(None);
+}
+
+/// Makes the operation invoked by the current worker cancellable until the
+/// registration is dropped.
+pub(crate) struct Registration {
+    cancel: Option<Arc<Cancel>>,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        if let Some(ref cancel) = self.cancel {
+            lock(&cancel.state).operation = ptr::null_mut();
+        }
+    }
+}
+
+/// Registers an operation about to be invoked on `session`. Returns a `Cancel`
+/// error if the call running on this thread is already cancelled.
+pub(crate) fn register_operation(
+    session: *mut raw::TEEC_Session,
+    operation: *mut raw::TEEC_Operation,
+) -> Result<Registration> {
+    let cancel = CURRENT.with(|current| current.borrow().clone());
+    if let Some(ref cancel) = cancel {
+        let mut state = lock(&cancel.state);
+        if state.requested {
+            return Err(Error::new(ErrorKind::Cancel));
+        }
+        // The client library sets the session when the command starts, a
+        // cancellation requested before would be lost.
+        unsafe { (*operation).session = session };
+        state.operation = operation;
+    }
+    Ok(Registration { cancel })
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+struct PoolState {
+    jobs: VecDeque<Job>,
+    idle: usize,
+    workers: usize,
+}
+
+/// The worker threads running the blocking calls. A worker is started when
+/// all the others are busy, up to `MAX_WORKERS`, and exits after being idle
+/// for a while.
+struct Pool {
+    state: Mutex<PoolState>,
+    available: Condvar,
+}
+
+impl Pool {
+    fn get() -> &'static Pool {
+        static INIT: Once = Once::new();
+        static mut POOL: *const Pool = ptr::null();
+        unsafe {
+            INIT.call_once(|| {
+                POOL = Box::into_raw(Box::new(Pool {
+                    state: Mutex::new(PoolState {
+                        jobs: VecDeque::new(),
+                        idle: 0,
+                        workers: 0,
+                    }),
+                    available: Condvar::new(),
+                }))
+            });
+            &*POOL
+        }
+    }
+
+    fn execute(&'static self, job: Job) {
+        let mut state = lock(&self.state);
+        state.jobs.push_back(job);
+        if state.jobs.len() > state.idle && state.workers < MAX_WORKERS {
+            state.workers += 1;
+            drop(state);
+            thread::Builder::new()
+                .name("optee-teec-worker".into())
+                .spawn(move || self.work())
+                .expect("failed to spawn a worker thread");
+        } else {
+            self.available.notify_one();
+        }
+    }
+
+    fn work(&self) {
+        let mut state = lock(&self.state);
+        loop {
+            if let Some(job) = state.jobs.pop_front() {
+                drop(state);
+                job();
+                state = lock(&self.state);
+                continue;
+            }
+            state.idle += 1;
+            let (next, timeout) = self
+                .available
+                .wait_timeout(state, WORKER_KEEP_ALIVE)
+                .unwrap_or_else(|e| e.into_inner());
+            state = next;
+            state.idle -= 1;
+            if timeout.timed_out() && state.jobs.is_empty() {
+                state.workers -= 1;
+                return;
+            }
+        }
+    }
+}
+
+struct TimerEntry {
+    deadline: Instant,
+    // Keeps the entries with the same deadline in the order they were added.
+    seq: u64,
+    expire: Job,
+}
+
+impl PartialEq for TimerEntry {
+    fn eq(&self, other: &TimerEntry) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for TimerEntry {}
+
+impl PartialOrd for TimerEntry {
+    fn partial_cmp(&self, other: &TimerEntry) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TimerEntry {
+    // Reversed, so that the heap yields the earliest deadline first.
+    fn cmp(&self, other: &TimerEntry) -> Ordering {
+        other
+            .deadline
+            .cmp(&self.deadline)
+            .then(other.seq.cmp(&self.seq))
+    }
+}
+
+struct TimerState {
+    entries: BinaryHeap<TimerEntry>,
+    next_seq: u64,
+}
+
+/// The thread running the timeouts of all the calls, started with the first
+/// timeout.
+struct Timer {
+    state: Mutex<TimerState>,
+    changed: Condvar,
+}
+
+impl Timer {
+    fn get() -> &'static Timer {
+        static INIT: Once = Once::new();
+        static mut TIMER: *const Timer = ptr::null();
+        unsafe {
+            INIT.call_once(|| {
+                let timer: &'static Timer = &*Box::into_raw(Box::new(Timer {
+                    state: Mutex::new(TimerState {
+                        entries: BinaryHeap::new(),
+                        next_seq: 0,
+                    }),
+                    changed: Condvar::new(),
+                }));
+                thread::Builder::new()
+                    .name("optee-teec-timer".into())
+                    .spawn(move || timer.run())
+                    .expect("failed to spawn the timer thread");
+                TIMER = timer;
+            });
+            &*TIMER
+        }
+    }
+
+    /// Runs `expire` on the timer thread at `deadline`.
+    fn schedule(&self, deadline: Instant, expire: Job) {
+        let mut state = lock(&self.state);
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        state.entries.push(TimerEntry {
+            deadline,
+            seq,
+            expire,
+        });
+        drop(state);
+        self.changed.notify_one();
+    }
+
+    fn run(&self) {
+        let mut state = lock(&self.state);
+        loop {
+            let now = Instant::now();
+            let deadline = state.entries.peek().map(|entry| entry.deadline);
+            state = match deadline {
+                Some(deadline) if deadline <= now => {
+                    let entry = state.entries.pop().unwrap();
+                    drop(state);
+                    (entry.expire)();
+                    lock(&self.state)
+                }
+                Some(deadline) => {
+                    self.changed
+                        .wait_timeout(state, deadline - now)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0
+                }
+                None => self.changed.wait(state).unwrap_or_else(|e| e.into_inner()),
+            };
+        }
+    }
+}
+
+fn spawn<T, F>(f: F) -> AsyncCall<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            finished: false,
+            result: None,
+            waker: None,
+        }),
+        cancel: Arc::new(Cancel {
+            state: Mutex::new(CancelState {
+                requested: false,
+                operation: ptr::null_mut(),
+            }),
+        }),
+    });
+    let job = shared.clone();
+    Pool::get().execute(Box::new(move || {
+        CURRENT.with(|current| *current.borrow_mut() = Some(job.cancel.clone()));
+        let result = panic::catch_unwind(AssertUnwindSafe(f));
+        CURRENT.with(|current| *current.borrow_mut() = None);
+        job.complete(lock(&job.state), result);
+    }));
+    AsyncCall { shared }
+}
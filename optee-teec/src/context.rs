@@ -1,13 +1,62 @@
-use crate::{Error, Operation, Result, Session, Uuid};
+use crate::{Error, ErrorKind, Operation, Result, Session, Uuid};
 use crate::{Param, ParamNone};
 use libc;
 use optee_teec_sys as raw;
+use std::cell::UnsafeCell;
+use std::ffi::CString;
+use std::mem;
 use std::ptr;
+use std::sync::Arc;
 
 /// An abstraction of the logical connection between a client application and a
 /// TEE.
+///
+/// A context is a cheap handle which can be cloned and shared between threads.
+/// Sessions borrow it immutably, so several sessions, to one or more trusted
+/// applications, can be open at the same time. The connection is finalized
+/// when the last handle and session are dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// let ctx = Context::new()?;
+/// let workers: Vec<_> = (0..4)
+///     .map(|_| {
+///         let ctx = ctx.clone();
+///         thread::spawn(move || -> optee_teec::Result<()> {
+///             let mut session = ctx.open_session(uuid)?;
+///             session.invoke_command(0, &mut operation)
+///         })
+///     })
+///     .collect();
+/// ```
+#[derive(Clone)]
 pub struct Context {
-    raw: raw::TEEC_Context,
+    inner: Arc<ContextInner>,
+}
+
+pub(crate) struct ContextInner {
+    raw: UnsafeCell<raw::TEEC_Context>,
+}
+
+// The TEE client library serializes the requests on a context, and the raw
+// context is only mutated by TEEC_InitializeContext and TEEC_FinalizeContext,
+// which get exclusive access.
+unsafe impl Send for ContextInner {}
+unsafe impl Sync for ContextInner {}
+
+impl ContextInner {
+    pub(crate) fn as_raw_ptr(&self) -> *mut raw::TEEC_Context {
+        self.raw.get()
+    }
+}
+
+impl Drop for ContextInner {
+    fn drop(&mut self) {
+        unsafe {
+            raw::TEEC_FinalizeContext(self.raw.get());
+        }
+    }
 }
 
 impl Context {
@@ -19,7 +68,27 @@ impl Context {
     /// let ctx = Context::new().unwrap();
     /// ```
     pub fn new() -> Result<Context> {
-        Context::new_raw(0, true).map(|raw| Context { raw })
+        ContextBuilder::new().build()
+    }
+
+    /// Checks whether a TEE is available, without keeping a context open.
+    ///
+    /// Returns `Ok(false)` if there is no TEE device, e.g. on a machine
+    /// without OP-TEE or when the driver is not loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// if !Context::probe()? {
+    ///     println!("No TEE found, falling back to software keys");
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) Any other error of `TEEC_InitializeContext`.
+    pub fn probe() -> Result<bool> {
+        ContextBuilder::new().probe()
     }
 
     /// Creates a raw TEE client context with implementation defined parameters.
@@ -29,6 +98,8 @@ impl Context {
     /// ```
     /// let raw_ctx: optee_teec_sys::TEEC_Context = Context::new_raw(0, true).unwrap();
     /// ```
+    #[deprecated(note = "the fields of the raw context depend on the version of the \
+                         client library, use `ContextBuilder` instead")]
     pub fn new_raw(fd: libc::c_int, reg_mem: bool) -> Result<raw::TEEC_Context> {
         let mut raw_ctx = raw::TEEC_Context { fd, reg_mem };
         unsafe {
@@ -48,7 +119,11 @@ impl Context {
     /// let mut raw_ptr: *mut optee_teec_sys::TEEC_Context = ctx.as_mut_raw_ptr();
     /// ```
     pub fn as_mut_raw_ptr(&mut self) -> *mut raw::TEEC_Context {
-        &mut self.raw
+        self.inner.as_raw_ptr()
+    }
+
+    pub(crate) fn inner(&self) -> &Arc<ContextInner> {
+        &self.inner
     }
 
     /// Opens a new session with the specified trusted application.
@@ -58,11 +133,11 @@ impl Context {
     /// # Examples
     ///
     /// ```
-    /// let mut ctx = Context::new().unwrap();
+    /// let ctx = Context::new().unwrap();
     /// let uuid = Uuid::parse_str("8abcf200-2450-11e4-abe2-0002a5d5c51b").unwrap();
     /// let session = ctx.open_session(uuid).unwrap();
     /// ```
-    pub fn open_session(&mut self, uuid: Uuid) -> Result<Session> {
+    pub fn open_session(&self, uuid: Uuid) -> Result<Session> {
         Session::new(
             self,
             uuid,
@@ -78,14 +153,14 @@ impl Context {
     /// # Examples
     ///
     /// ```
-    /// let mut ctx = Context::new().unwrap();
+    /// let ctx = Context::new().unwrap();
     /// let uuid = Uuid::parse_str("8abcf200-2450-11e4-abe2-0002a5d5c51b").unwrap();
     /// let p0 = ParamValue(42, 0, ParamType::ValueInout);
     /// let mut operation = Operation::new(0, p0, ParamNone, ParamNone, ParamNone);
     /// let session = ctx.open_session_with_operation(uuid, operation).unwrap();
     /// ```
     pub fn open_session_with_operation<A: Param, B: Param, C: Param, D: Param>(
-        &mut self,
+        &self,
         uuid: Uuid,
         operation: &mut Operation<A, B, C, D>,
     ) -> Result<Session> {
@@ -93,10 +168,98 @@ impl Context {
     }
 }
 
-impl Drop for Context {
-    fn drop(&mut self) {
-        unsafe {
-            raw::TEEC_FinalizeContext(&mut self.raw);
+/// Configures and creates a [Context](Context).
+///
+/// # Examples
+///
+/// ```no_run
+/// let ctx = ContextBuilder::new().name("optee-tz").build()?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct ContextBuilder {
+    name: Option<String>,
+    #[cfg(feature = "context_reg_mem")]
+    register_memrefs: bool,
+}
+
+impl ContextBuilder {
+    /// Creates a builder for a context on the default TEE selected by the
+    /// client library.
+    pub fn new() -> ContextBuilder {
+        ContextBuilder {
+            name: None,
+            #[cfg(feature = "context_reg_mem")]
+            register_memrefs: true,
+        }
+    }
+
+    /// Sets the name passed to `TEEC_InitializeContext`, which selects the
+    /// TEE by its implementation rather than by a device path. The OP-TEE
+    /// client library looks for a device itself and only accepts `optee-tz`,
+    /// the default TEE is used if no name is set.
+    pub fn name<S: Into<String>>(mut self, name: S) -> ContextBuilder {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Sets whether temporary memory references are registered with the
+    /// driver when it supports it, which avoids a copy. Otherwise they are
+    /// copied through shared memory allocated by the driver.
+    ///
+    /// This writes the `reg_mem` field of the raw context after it is
+    /// initialized, which is only available with the `context_reg_mem` feature
+    /// since the fields depend on the version of the client library.
+    #[cfg(feature = "context_reg_mem")]
+    pub fn register_memrefs(mut self, register_memrefs: bool) -> ContextBuilder {
+        self.register_memrefs = register_memrefs;
+        self
+    }
+
+    /// Creates the context.
+    ///
+    /// # Errors
+    ///
+    /// 1) `ItemNotFound`: If no TEE with the name is found.
+    /// 2) `BadParameters`: If the name contains a nul byte.
+    pub fn build(&self) -> Result<Context> {
+        let name = match self.name {
+            Some(ref name) => Some(
+                CString::new(name.as_bytes()).map_err(|_| Error::new(ErrorKind::BadParameters))?,
+            ),
+            None => None,
+        };
+        let name = name.as_ref().map_or(ptr::null(), |name| name.as_ptr());
+        // The client library initializes the context.
+        let mut raw_ctx: raw::TEEC_Context = unsafe { mem::zeroed() };
+        match unsafe { raw::TEEC_InitializeContext(name, &mut raw_ctx) } {
+            raw::TEEC_SUCCESS => (),
+            code => return Err(Error::from_raw_error(code)),
+        }
+        #[cfg(feature = "context_reg_mem")]
+        {
+            raw_ctx.reg_mem &= self.register_memrefs;
         }
+        Ok(Context {
+            inner: Arc::new(ContextInner {
+                raw: UnsafeCell::new(raw_ctx),
+            }),
+        })
+    }
+
+    /// Checks whether the TEE is available, see [Context::probe](Context::probe).
+    pub fn probe(&self) -> Result<bool> {
+        match self.build() {
+            Ok(_) => Ok(true),
+            Err(e) => match e.kind() {
+                ErrorKind::ItemNotFound | ErrorKind::Communication => Ok(false),
+                _ => Err(e),
+            },
+        }
+    }
+}
+
+impl Default for ContextBuilder {
+    fn default() -> ContextBuilder {
+        ContextBuilder::new()
     }
 }
@@ -1,5 +1,7 @@
+use optee_error::CustomError;
 use optee_teec_sys as raw;
 use std::fmt;
+use std::io;
 
 /// A specialized [`Result`](https://doc.rust-lang.org/std/result/enum.Result.html)
 /// type for TEE operations.
@@ -8,7 +10,7 @@ use std::fmt;
 ///
 /// ``` no_run
 /// fn main() -> optee_teec::Result<()> {
-///     let mut ctx = Context::new()?;
+///     let ctx = Context::new()?;
 /// }
 /// ````
 pub type Result<T> = std::result::Result<T, Error>;
@@ -66,6 +68,8 @@ pub enum ErrorKind {
     TargetDead = 0xFFFF3024,
     /// Unknown error.
     Unknown,
+    /// An application-defined error, see [CustomError](trait.CustomError.html).
+    Custom = optee_error::CUSTOM_ERROR_BASE,
 }
 
 impl ErrorKind {
@@ -95,6 +99,7 @@ impl ErrorKind {
             ErrorKind::ExternalCancel => "Undocumented.",
             ErrorKind::TargetDead => "Trusted Application has panicked during the operation.",
             ErrorKind::Unknown => "Unknown error.",
+            ErrorKind::Custom => "Application-defined error.",
         }
     }
 }
@@ -147,6 +152,7 @@ impl Error {
             raw::TEEC_ERROR_SHORT_BUFFER => ErrorKind::ShortBuffer,
             raw::TEEC_ERROR_EXTERNAL_CANCEL => ErrorKind::ExternalCancel,
             raw::TEEC_ERROR_TARGET_DEAD => ErrorKind::TargetDead,
+            code if optee_error::is_custom_error(code) => ErrorKind::Custom,
             _ => ErrorKind::Unknown,
         }
     }
@@ -160,6 +166,37 @@ impl Error {
     pub fn message(&self) -> &str {
         self.kind().as_str()
     }
+
+    /// Returns the application-defined error, if the code belongs to `E`.
+    ///
+    /// # Examples
+    ///
+    /// ``` no_run
+    /// use optee_teec::{CustomError, Error};
+    ///
+    /// #[derive(Clone, Copy, CustomError, Debug, PartialEq)]
+    /// pub enum WalletError {
+    ///     PinBlocked,
+    /// }
+    ///
+    /// let error = Error::from(WalletError::PinBlocked);
+    /// assert_eq!(error.custom(), Some(WalletError::PinBlocked));
+    /// ```
+    pub fn custom<E: CustomError>(&self) -> Option<E> {
+        E::from_code(self.code)
+    }
+
+    /// Converts an `io::Error`. An `io::Error` created from an `Error` gives back the original
+    /// error.
+    ///
+    /// This is not a `From` implementation, which would overlap with the conversion from the
+    /// [CustomError](trait.CustomError.html) types.
+    pub fn from_io_error(error: io::Error) -> Error {
+        if let Some(error) = error.get_ref().and_then(|e| e.downcast_ref::<Error>()) {
+            return Error { code: error.code };
+        }
+        Error::from_raw_error(optee_error::io::error_code(error.kind()))
+    }
 }
 
 impl fmt::Debug for Error {
@@ -186,3 +223,16 @@ impl From<ErrorKind> for Error {
         Error { code: kind as u32 }
     }
 }
+
+impl<E: CustomError> From<E> for Error {
+    #[inline]
+    fn from(error: E) -> Error {
+        Error { code: error.code() }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(error: Error) -> io::Error {
+        io::Error::new(optee_error::io::error_kind(error.code), error)
+    }
+}
@@ -1,13 +1,24 @@
-pub use self::context::Context;
+#[cfg(feature = "async")]
+pub use self::async_session::{AsyncCall, AsyncSession, CancelHandle};
+pub use self::context::{Context, ContextBuilder};
 pub use self::error::{Error, ErrorKind, Result};
+pub use optee_error::CustomError;
 pub use self::operation::Operation;
 pub use self::parameter::{Param, ParamNone, ParamTmpRef, ParamType, ParamTypes, ParamValue};
 pub use self::session::{ConnectionMethods, Session};
+pub use self::supervisor::{RestartPolicy, SupervisedSession, SupervisedSessionBuilder};
+#[cfg(feature = "trace_capture")]
+pub use self::trace::{TraceLevel, TraceRecord, TRACE_CAPTURE_COMMAND_ID};
 pub use self::uuid::Uuid;
 
+#[cfg(feature = "async")]
+mod async_session;
 mod context;
 mod error;
 mod operation;
 mod parameter;
 mod session;
+mod supervisor;
+#[cfg(feature = "trace_capture")]
+mod trace;
 mod uuid;
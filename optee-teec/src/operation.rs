@@ -14,6 +14,9 @@ pub struct Operation<A, B, C, D> {
     phantom3: PhantomData<D>,
 }
 
+// The raw pointers of the operation are owned or borrowed by the parameters.
+unsafe impl<A: Send, B: Send, C: Send, D: Send> Send for Operation<A, B, C, D> {}
+
 impl<A: Param, B: Param, C: Param, D: Param> Operation<A, B, C, D> {
     pub fn new(started: u32, mut p0: A, mut p1: B, mut p2: C, mut p3: D) -> Operation<A, B, C, D> {
         let mut raw_op: raw::TEEC_Operation = unsafe { mem::zeroed() };
@@ -1,8 +1,10 @@
 use libc;
 use optee_teec_sys as raw;
-use std::ptr;
 use std::marker;
+use std::ptr;
+use std::sync::Arc;
 
+use crate::context::ContextInner;
 use crate::Param;
 use crate::{Context, Error, Operation, Result, Uuid};
 
@@ -24,20 +26,36 @@ pub enum ConnectionMethods {
 }
 
 /// Represents a connection between a client application and a trusted application.
+///
+/// A session can be moved to another thread, but commands of one session are
+/// invoked one at a time. Open several sessions to invoke commands concurrently.
 pub struct Session<'ctx> {
     raw: raw::TEEC_Session,
-    _marker: marker::PhantomData<&'ctx mut Context>,
+    // Keeps the context alive as long as the session, whatever the lifetime.
+    _context: Arc<ContextInner>,
+    _marker: marker::PhantomData<&'ctx Context>,
 }
 
+// The raw session only points to the context, which is `Send + Sync`.
+unsafe impl<'ctx> Send for Session<'ctx> {}
+
 impl<'ctx> Session<'ctx> {
     /// Initializes a TEE session object with specified context and uuid.
     pub fn new<A: Param, B: Param, C: Param, D: Param>(
-        context: &'ctx mut Context,
+        context: &'ctx Context,
+        uuid: Uuid,
+        operation: Option<&mut Operation<A, B, C, D>>,
+    ) -> Result<Self> {
+        Self::open(context.inner().clone(), uuid, operation)
+    }
+
+    pub(crate) fn open<A: Param, B: Param, C: Param, D: Param>(
+        context: Arc<ContextInner>,
         uuid: Uuid,
         operation: Option<&mut Operation<A, B, C, D>>,
     ) -> Result<Self> {
         let mut raw_session = raw::TEEC_Session {
-            ctx: context.as_mut_raw_ptr(),
+            ctx: context.as_raw_ptr(),
             session_id: 0,
         };
         let mut err_origin: u32 = 0;
@@ -47,7 +65,7 @@ impl<'ctx> Session<'ctx> {
         };
         unsafe {
             match raw::TEEC_OpenSession(
-                context.as_mut_raw_ptr(),
+                context.as_raw_ptr(),
                 &mut raw_session,
                 uuid.as_raw_ptr(),
                 ConnectionMethods::LoginPublic as u32,
@@ -55,7 +73,11 @@ impl<'ctx> Session<'ctx> {
                 raw_operation,
                 &mut err_origin,
             ) {
-                raw::TEEC_SUCCESS => Ok(Self { raw: raw_session,  _marker: marker::PhantomData }),
+                raw::TEEC_SUCCESS => Ok(Self {
+                    raw: raw_session,
+                    _context: context,
+                    _marker: marker::PhantomData,
+                }),
                 code => Err(Error::from_raw_error(code)),
             }
         }
@@ -72,6 +94,9 @@ impl<'ctx> Session<'ctx> {
         command_id: u32,
         operation: &mut Operation<A, B, C, D>,
     ) -> Result<()> {
+        #[cfg(feature = "async")]
+        let _registration =
+            crate::async_session::register_operation(&mut self.raw, operation.as_mut_raw_ptr())?;
         let mut err_origin: u32 = 0;
         unsafe {
             match raw::TEEC_InvokeCommand(
@@ -0,0 +1,250 @@
+use crate::{Context, Error, ErrorKind, Operation, Param, ParamNone, Result, Session, Uuid};
+use std::thread;
+use std::time::Duration;
+
+/// How a [SupervisedSession](SupervisedSession) reopens its session after the
+/// trusted application died.
+///
+/// The first attempt is immediate, then the delay before each attempt starts
+/// at `initial_backoff` and doubles up to `max_backoff`.
+///
+/// # Examples
+///
+/// ```no_run
+/// let policy = RestartPolicy::new()
+///     .max_attempts(5)
+///     .backoff(Duration::from_millis(10), Duration::from_secs(1));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct RestartPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    retry_command: bool,
+}
+
+impl RestartPolicy {
+    /// Creates the default policy: 3 attempts, with a backoff from 100
+    /// milliseconds to 5 seconds, and the failed command is invoked again.
+    pub fn new() -> RestartPolicy {
+        RestartPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            retry_command: true,
+        }
+    }
+
+    /// Sets the number of attempts to reopen the session before giving up.
+    pub fn max_attempts(mut self, max_attempts: u32) -> RestartPolicy {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Sets the delay before the second attempt and the largest delay.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> RestartPolicy {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Sets whether the command which met the dead trusted application is
+    /// invoked again, once, on the new session. Commands which are not
+    /// idempotent should not be retried.
+    pub fn retry_command(mut self, retry_command: bool) -> RestartPolicy {
+        self.retry_command = retry_command;
+        self
+    }
+}
+
+impl Default for RestartPolicy {
+    fn default() -> RestartPolicy {
+        RestartPolicy::new()
+    }
+}
+
+type Setup<'ctx> = Box<dyn FnMut(&mut Session<'ctx>) -> Result<()> + 'ctx>;
+
+/// Builds a [SupervisedSession](SupervisedSession).
+pub struct SupervisedSessionBuilder<'ctx, A, B, C, D> {
+    context: &'ctx Context,
+    uuid: Uuid,
+    operation: Option<Operation<A, B, C, D>>,
+    policy: RestartPolicy,
+    setup: Option<Setup<'ctx>>,
+}
+
+impl<'ctx> SupervisedSessionBuilder<'ctx, ParamNone, ParamNone, ParamNone, ParamNone> {
+    /// Sets the operation passed to the trusted application each time the
+    /// session is opened.
+    pub fn operation<A: Param, B: Param, C: Param, D: Param>(
+        self,
+        operation: Operation<A, B, C, D>,
+    ) -> SupervisedSessionBuilder<'ctx, A, B, C, D> {
+        SupervisedSessionBuilder {
+            context: self.context,
+            uuid: self.uuid,
+            operation: Some(operation),
+            policy: self.policy,
+            setup: self.setup,
+        }
+    }
+}
+
+impl<'ctx, A: Param, B: Param, C: Param, D: Param> SupervisedSessionBuilder<'ctx, A, B, C, D> {
+    /// Sets the restart policy, [RestartPolicy::new](RestartPolicy::new) by
+    /// default.
+    pub fn policy(mut self, policy: RestartPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets the commands run each time the session is opened, e.g. to load a
+    /// key in the trusted application. An error fails the attempt.
+    pub fn setup<F>(mut self, setup: F) -> Self
+    where
+        F: FnMut(&mut Session<'ctx>) -> Result<()> + 'ctx,
+    {
+        self.setup = Some(Box::new(setup));
+        self
+    }
+
+    /// Opens the session and runs the setup commands.
+    pub fn open(self) -> Result<SupervisedSession<'ctx, A, B, C, D>> {
+        let mut session = SupervisedSession {
+            context: self.context,
+            uuid: self.uuid,
+            operation: self.operation,
+            policy: self.policy,
+            setup: self.setup,
+            session: None,
+            restarts: 0,
+        };
+        session.start()?;
+        Ok(session)
+    }
+}
+
+/// A session which is reopened when the trusted application dies, i.e. when
+/// a command fails with `TargetDead`, e.g. after a panic in the trusted
+/// application.
+///
+/// The session is reopened following its [RestartPolicy](RestartPolicy), with
+/// the stored open session operation, then the setup commands are run again.
+///
+/// # Examples
+///
+/// ```no_run
+/// let ctx = Context::new()?;
+/// let mut session = SupervisedSession::builder(&ctx, uuid)
+///     .setup(|session| {
+///         prepare_aes(session, ENCODE)?;
+///         set_key(session, &key)
+///     })
+///     .open()?;
+/// session.invoke_command(Command::Cipher as u32, &mut operation)?;
+/// println!("restarted {} times", session.restarts());
+/// ```
+pub struct SupervisedSession<'ctx, A = ParamNone, B = ParamNone, C = ParamNone, D = ParamNone> {
+    context: &'ctx Context,
+    uuid: Uuid,
+    operation: Option<Operation<A, B, C, D>>,
+    policy: RestartPolicy,
+    setup: Option<Setup<'ctx>>,
+    session: Option<Session<'ctx>>,
+    restarts: u32,
+}
+
+impl<'ctx> SupervisedSession<'ctx> {
+    /// Creates a builder for a session with the trusted application `uuid`.
+    pub fn builder(
+        context: &'ctx Context,
+        uuid: Uuid,
+    ) -> SupervisedSessionBuilder<'ctx, ParamNone, ParamNone, ParamNone, ParamNone> {
+        SupervisedSessionBuilder {
+            context,
+            uuid,
+            operation: None,
+            policy: RestartPolicy::new(),
+            setup: None,
+        }
+    }
+}
+
+impl<'ctx, A: Param, B: Param, C: Param, D: Param> SupervisedSession<'ctx, A, B, C, D> {
+    /// Invokes a command, reopening the session if the trusted application is
+    /// dead.
+    ///
+    /// # Errors
+    ///
+    /// 1) `TargetDead`: If the command killed the trusted application and it
+    ///    is not retried, or if it killed the new session as well.
+    /// 2) The error of the last attempt, if the session cannot be reopened.
+    pub fn invoke_command<E: Param, F: Param, G: Param, H: Param>(
+        &mut self,
+        command_id: u32,
+        operation: &mut Operation<E, F, G, H>,
+    ) -> Result<()> {
+        let mut retried = false;
+        loop {
+            if self.session.is_none() {
+                self.restart()?;
+            }
+            let session = self.session.as_mut().unwrap();
+            match session.invoke_command(command_id, operation) {
+                Err(ref e) if e.kind() == ErrorKind::TargetDead => {
+                    self.session = None;
+                    if retried || !self.policy.retry_command {
+                        return Err(Error::new(ErrorKind::TargetDead));
+                    }
+                    retried = true;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Returns the current session, reopening it if the trusted application is
+    /// dead. The session is not supervised while it is borrowed.
+    pub fn session(&mut self) -> Result<&mut Session<'ctx>> {
+        if self.session.is_none() {
+            self.restart()?;
+        }
+        Ok(self.session.as_mut().unwrap())
+    }
+
+    /// Returns the number of times the session has been reopened.
+    pub fn restarts(&self) -> u32 {
+        self.restarts
+    }
+
+    fn start(&mut self) -> Result<()> {
+        let mut session = Session::open(
+            self.context.inner().clone(),
+            self.uuid.clone(),
+            self.operation.as_mut(),
+        )?;
+        if let Some(ref mut setup) = self.setup {
+            setup(&mut session)?;
+        }
+        self.session = Some(session);
+        Ok(())
+    }
+
+    fn restart(&mut self) -> Result<()> {
+        let mut backoff = self.policy.initial_backoff;
+        let mut result = Err(Error::new(ErrorKind::TargetDead));
+        for attempt in 0..self.policy.max_attempts {
+            if attempt > 0 {
+                thread::sleep(backoff);
+                backoff = (backoff * 2).min(self.policy.max_backoff);
+            }
+            result = self.start();
+            if result.is_ok() {
+                self.restarts += 1;
+                break;
+            }
+        }
+        result
+    }
+}
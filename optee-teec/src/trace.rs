@@ -0,0 +1,110 @@
+use crate::{Error, ErrorKind, Operation, ParamNone, ParamTmpRef, Result, Session};
+
+/// The command identifier reserved by Trusted Applications built with the `trace_capture` feature
+/// of `optee-utee`.
+pub const TRACE_CAPTURE_COMMAND_ID: u32 = 0xFFFF_FFF0;
+
+/// Initial size of the buffer receiving the records, it grows if the TA needs more.
+const INITIAL_BUFFER_LEN: usize = 4096;
+
+/// The trace levels of OP-TEE.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum TraceLevel {
+    Error = 1,
+    Info = 2,
+    Debug = 3,
+    Flow = 4,
+}
+
+/// A message printed to the trace output by a Trusted Application.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TraceRecord {
+    /// The level of the message, `None` for `trace_print!` and `trace_println!`.
+    pub level: Option<TraceLevel>,
+    /// The source file and line of the message, if known.
+    pub location: Option<(String, u32)>,
+    /// The message, without the trailing newline.
+    pub message: String,
+}
+
+impl<'ctx> Session<'ctx> {
+    /// Take the messages the Trusted Application printed to its trace output since the last call.
+    ///
+    /// The TA must be built with the `trace_capture` feature of `optee-utee`. Records are kept per
+    /// TA instance, so with a multi-session TA they include the messages of every session.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// session.invoke_command(Command::IncValue as u32, &mut operation)?;
+    /// let records = session.take_trace_records()?;
+    /// assert!(records.iter().any(|r| r.message == "value incremented to 129"));
+    /// ```
+    pub fn take_trace_records(&mut self) -> Result<Vec<TraceRecord>> {
+        let mut len = INITIAL_BUFFER_LEN;
+        loop {
+            let mut buffer = vec![0u8; len];
+            let p0 = ParamTmpRef::new_output(&mut buffer);
+            let mut operation = Operation::new(0, p0, ParamNone, ParamNone, ParamNone);
+            let res = self.invoke_command(TRACE_CAPTURE_COMMAND_ID, &mut operation);
+            let updated_size = operation.parameters().0.updated_size();
+            match res {
+                Ok(()) => {
+                    buffer.truncate(updated_size);
+                    return parse_records(&buffer);
+                }
+                Err(ref e) if e.kind() == ErrorKind::ShortBuffer && updated_size > len => {
+                    len = updated_size;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+fn parse_records(mut data: &[u8]) -> Result<Vec<TraceRecord>> {
+    let mut records = Vec::new();
+    while !data.is_empty() {
+        let level = match take(&mut data, 1)?[0] {
+            0 => None,
+            1 => Some(TraceLevel::Error),
+            2 => Some(TraceLevel::Info),
+            3 => Some(TraceLevel::Debug),
+            4 => Some(TraceLevel::Flow),
+            _ => return Err(Error::new(ErrorKind::BadFormat)),
+        };
+        let line = take_u32(&mut data)?;
+        let file = take_string(&mut data)?;
+        let message = take_string(&mut data)?;
+        records.push(TraceRecord {
+            level,
+            location: if file.is_empty() {
+                None
+            } else {
+                Some((file, line))
+            },
+            message,
+        });
+    }
+    Ok(records)
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if data.len() < len {
+        return Err(Error::new(ErrorKind::BadFormat));
+    }
+    let (head, tail) = data.split_at(len);
+    *data = tail;
+    Ok(head)
+}
+
+fn take_u32(data: &mut &[u8]) -> Result<u32> {
+    let bytes = take(data, 4)?;
+    Ok(bytes.iter().fold(0, |acc, &b| (acc << 8) | u32::from(b)))
+}
+
+fn take_string(data: &mut &[u8]) -> Result<String> {
+    let len = take_u32(data)? as usize;
+    let bytes = take(data, len)?;
+    String::from_utf8(bytes.to_vec()).map_err(|_| Error::new(ErrorKind::BadFormat))
+}
@@ -85,6 +85,33 @@ impl Uuid {
     }
 }
 
+/// Converts a UUID parsed at compile time, e.g. the `UUID` constant of a proto
+/// crate.
+///
+/// # Examples
+///
+/// ```no_run
+/// let session = ctx.open_session(Uuid::from(proto::UUID))?;
+/// ```
+impl From<optee_uuid::Uuid> for Uuid {
+    fn from(uuid: optee_uuid::Uuid) -> Uuid {
+        Self {
+            raw: uuid.to_teec_uuid(),
+        }
+    }
+}
+
+impl Clone for Uuid {
+    fn clone(&self) -> Uuid {
+        Self::new_raw(
+            self.raw.timeLow,
+            self.raw.timeMid,
+            self.raw.timeHiAndVersion,
+            self.raw.clockSeqAndNode,
+        )
+    }
+}
+
 impl fmt::Display for Uuid {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -0,0 +1,8 @@
+fn main() {
+    // Without the `std` feature the crate provides the allocation error handler, which has to be
+    // declared with a feature gate before Rust 1.68 and has a default afterwards.
+    println!("cargo:rustc-check-cfg=cfg(alloc_error_handler_required)");
+    if version_check::is_min_version("1.68.0") == Some(false) {
+        println!("cargo:rustc-cfg=alloc_error_handler_required");
+    }
+}
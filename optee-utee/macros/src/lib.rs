@@ -1,3 +1,5 @@
+#![recursion_limit = "128"]
+
 extern crate proc_macro;
 
 use proc_macro::TokenStream;
@@ -332,6 +334,12 @@ pub fn ta_invoke_command(_args: TokenStream, input: TokenStream) -> TokenStream
                 params: &mut [optee_utee_sys::TEE_Param; 4],
             ) -> optee_utee_sys::TEE_Result {
                 let mut parameters = Parameters::from_raw(params, param_types);
+                if let Some(res) = optee_utee::trace::_handle_capture_command(cmd_id, &mut parameters) {
+                    return match res {
+                        Ok(_) => optee_utee_sys::TEE_SUCCESS,
+                        Err(e) => e.raw_code()
+                    };
+                }
                 match #ident(cmd_id, &mut parameters) {
                     Ok(_) => {
                         optee_utee_sys::TEE_SUCCESS
@@ -370,10 +378,17 @@ pub fn ta_invoke_command(_args: TokenStream, input: TokenStream) -> TokenStream
                         return optee_utee_sys::TEE_ERROR_SECURITY;
                     }
                     let mut parameters = Parameters::from_raw(params, param_types);
-                    let mut b = unsafe {Box::from_raw(sess_ctx as *mut #t)};
-                    match #ident(&mut b, cmd_id, &mut parameters) {
+                    if let Some(res) = optee_utee::trace::_handle_capture_command(cmd_id, &mut parameters) {
+                        return match res {
+                            Ok(_) => optee_utee_sys::TEE_SUCCESS,
+                            Err(e) => e.raw_code()
+                        };
+                    }
+                    // The session context is freed when the session is closed, not when a command
+                    // fails.
+                    let b = unsafe { &mut *(sess_ctx as *mut #t) };
+                    match #ident(b, cmd_id, &mut parameters) {
                         Ok(_) => {
-                            std::mem::forget(b);
                             optee_utee_sys::TEE_SUCCESS
                         },
                         Err(e) => e.raw_code()
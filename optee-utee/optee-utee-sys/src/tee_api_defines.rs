@@ -1,5 +1,5 @@
 use super::tee_api_types::*;
-use std::ptr::null;
+use core::ptr::null;
 
 pub const TEE_INT_CORE_API_SPEC_VERSION: u32 = 0x0000000A;
 
@@ -72,9 +72,13 @@ pub const TEE_PROPSET_CURRENT_TA: TEE_PropSetHandle = 0xFFFFFFFF as *mut _;
 pub const TEE_MEMORY_ACCESS_READ: u32 = 0x00000001;
 pub const TEE_MEMORY_ACCESS_WRITE: u32 = 0x00000002;
 pub const TEE_MEMORY_ACCESS_ANY_OWNER: u32 = 0x00000004;
+pub const TEE_MEMORY_ACCESS_NONSECURE: u32 = 0x10000000;
+pub const TEE_MEMORY_ACCESS_SECURE: u32 = 0x20000000;
 
 // Memory Management Constant
 pub const TEE_MALLOC_FILL_ZERO: u32 = 0x00000000;
+pub const TEE_MALLOC_NO_FILL: u32 = 0x00000001;
+pub const TEE_MALLOC_NO_SHARE: u32 = 0x00000002;
 
 // Other constants
 pub const TEE_STORAGE_PRIVATE: u32 = 0x00000001;
@@ -173,6 +177,19 @@ pub const TEE_ALG_ECDH_P224: u32 = 0x80002042;
 pub const TEE_ALG_ECDH_P256: u32 = 0x80003042;
 pub const TEE_ALG_ECDH_P384: u32 = 0x80004042;
 pub const TEE_ALG_ECDH_P521: u32 = 0x80005042;
+// OP-TEE extensions: key derivation functions
+pub const TEE_ALG_HKDF_MD5_DERIVE_KEY: u32 = 0x800010C0;
+pub const TEE_ALG_HKDF_SHA1_DERIVE_KEY: u32 = 0x800020C0;
+pub const TEE_ALG_HKDF_SHA224_DERIVE_KEY: u32 = 0x800030C0;
+pub const TEE_ALG_HKDF_SHA256_DERIVE_KEY: u32 = 0x800040C0;
+pub const TEE_ALG_HKDF_SHA384_DERIVE_KEY: u32 = 0x800050C0;
+pub const TEE_ALG_HKDF_SHA512_DERIVE_KEY: u32 = 0x800060C0;
+pub const TEE_ALG_CONCAT_KDF_SHA1_DERIVE_KEY: u32 = 0x800020C1;
+pub const TEE_ALG_CONCAT_KDF_SHA224_DERIVE_KEY: u32 = 0x800030C1;
+pub const TEE_ALG_CONCAT_KDF_SHA256_DERIVE_KEY: u32 = 0x800040C1;
+pub const TEE_ALG_CONCAT_KDF_SHA384_DERIVE_KEY: u32 = 0x800050C1;
+pub const TEE_ALG_CONCAT_KDF_SHA512_DERIVE_KEY: u32 = 0x800060C1;
+pub const TEE_ALG_PBKDF2_HMAC_SHA1_DERIVE_KEY: u32 = 0x800020C2;
 
 // Object Types
 pub const TEE_TYPE_AES: u32 = 0xA0000010;
@@ -196,6 +213,10 @@ pub const TEE_TYPE_ECDH_KEYPAIR: u32 = 0xA1000042;
 pub const TEE_TYPE_GENERIC_SECRET: u32 = 0xA0000000;
 pub const TEE_TYPE_CORRUPTED_OBJECT: u32 = 0xA00000BE;
 pub const TEE_TYPE_DATA: u32 = 0xA00000BF;
+// OP-TEE extensions: key derivation functions
+pub const TEE_TYPE_HKDF_IKM: u32 = 0xA10000C0;
+pub const TEE_TYPE_CONCAT_KDF_Z: u32 = 0xA10000C1;
+pub const TEE_TYPE_PBKDF2_PASSWORD: u32 = 0xA10000C2;
 
 // List of Object or Operation Attributes
 pub const TEE_ATTR_SECRET_VALUE: u32 = 0xC0000000;
@@ -226,8 +247,21 @@ pub const TEE_ATTR_ECC_PRIVATE_VALUE: u32 = 0xC0000341;
 pub const TEE_ATTR_ECC_CURVE: u32 = 0xF0000441;
 pub const TEE_ATTR_BIT_PROTECTED: u32 = 1 << 28;
 pub const TEE_ATTR_BIT_VALUE: u32 = 1 << 29;
+// OP-TEE extensions: key derivation functions
+pub const TEE_ATTR_HKDF_IKM: u32 = 0xC00001C0;
+pub const TEE_ATTR_HKDF_SALT: u32 = 0xD00002C0;
+pub const TEE_ATTR_HKDF_INFO: u32 = 0xD00003C0;
+pub const TEE_ATTR_HKDF_OKM_LENGTH: u32 = 0xF00004C0;
+pub const TEE_ATTR_CONCAT_KDF_Z: u32 = 0xC00001C1;
+pub const TEE_ATTR_CONCAT_KDF_OTHER_INFO: u32 = 0xD00002C1;
+pub const TEE_ATTR_CONCAT_KDF_DKM_LENGTH: u32 = 0xF00003C1;
+pub const TEE_ATTR_PBKDF2_PASSWORD: u32 = 0xC00001C2;
+pub const TEE_ATTR_PBKDF2_SALT: u32 = 0xD00002C2;
+pub const TEE_ATTR_PBKDF2_ITERATION_COUNT: u32 = 0xF00003C2;
+pub const TEE_ATTR_PBKDF2_DKM_LENGTH: u32 = 0xF00004C2;
 
 // List of Supported ECC Curves
+pub const TEE_CRYPTO_ELEMENT_NONE: u32 = 0x00000000;
 pub const TEE_ECC_CURVE_NIST_P192: u32 = 0x00000001;
 pub const TEE_ECC_CURVE_NIST_P224: u32 = 0x00000002;
 pub const TEE_ECC_CURVE_NIST_P256: u32 = 0x00000003;
@@ -1,11 +1,23 @@
-use crate::{Error, Result};
+use crate::{Error, ErrorKind, Result};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::{max, Ordering};
+use core::convert::TryFrom;
+use core::ops::{
+    Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Shr, ShrAssign, Sub,
+    SubAssign,
+};
+use core::str::FromStr;
+use core::{fmt, iter};
 use optee_utee_sys as raw;
-use std::{cmp::max, fmt};
 
 pub type BigIntUnit = u32;
 pub type BigIntFMMUnit = u32;
 pub type BigIntFMMContextUnit = u32;
 
+#[derive(Clone)]
 pub struct BigInt(Vec<BigIntUnit>);
 
 impl BigInt {
@@ -94,6 +106,24 @@ impl BigInt {
         unsafe { raw::TEE_BigIntGetBitCount(self.data_ptr()) }
     }
 
+    /// Set the bit at `bit_index` of the absolute value to `value`, the sign is kept.
+    ///
+    /// # Errors
+    ///
+    /// 1) `Overflow`: If `bit_index` is beyond the capacity the number was allocated with.
+    pub fn set_bit(&mut self, bit_index: u32, value: bool) -> Result<()> {
+        self.set_bit_impl(bit_index, value)
+    }
+
+    /// Copy the value of `src` into `self`, keeping the capacity of `self`.
+    ///
+    /// # Errors
+    ///
+    /// 1) `Overflow`: If the value of `src` does not fit in `self`.
+    pub fn assign(&mut self, src: &Self) -> Result<()> {
+        self.assign_impl(src)
+    }
+
     pub fn add(op1: &Self, op2: &Self) -> Self {
         let bits = max(Self::get_bit_count(op1), Self::get_bit_count(op2)) + 1;
         let mut res = Self::new(bits);
@@ -114,6 +144,10 @@ impl BigInt {
         res
     }
 
+    pub fn abs(op: &Self) -> Self {
+        Self::abs_impl(op)
+    }
+
     pub fn multiply(op1: &Self, op2: &Self) -> Self {
         let bits = Self::get_bit_count(op1) + Self::get_bit_count(op2);
         let mut res = Self::new(bits);
@@ -129,10 +163,10 @@ impl BigInt {
 
     // document defines wrong size for result quotient
     pub fn divide(op1: &Self, op2: &Self) -> (Self, Self) {
-        let q_bits = match op1.compare_big_int(op2) {
-            d if d >= 0 => max(1, Self::get_bit_count(op1) - Self::get_bit_count(op2)),
-            _ => 0,
-        };
+        let q_bits = max(
+            1,
+            Self::get_bit_count(op1).saturating_sub(Self::get_bit_count(op2)) + 1,
+        );
         let r_bits = Self::get_bit_count(op2);
         let mut quotient = Self::new(q_bits);
         let mut remainder = Self::new(r_bits);
@@ -205,13 +239,63 @@ impl BigInt {
         res
     }
 
+    /// Compute `op ^ exp mod n`.
+    ///
+    /// Odd moduli are handled with fast modular multiplication, as required by
+    /// `TEE_BigIntExpMod`, even moduli fall back to plain modular multiplications.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let p: BigInt = "0xfffffffb".parse()?;
+    /// let res = BigInt::exp_mod(&BigInt::from(3), &BigInt::from(100), &p)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadParameters`: If `exp` is negative or `n` is not greater than 1.
+    pub fn exp_mod(op: &Self, exp: &Self, n: &Self) -> Result<Self> {
+        if exp.compare_s32(0) < 0 || n.compare_s32(1) <= 0 {
+            return Err(Error::new(ErrorKind::BadParameters));
+        }
+        if n.get_bit(0) {
+            Self::exp_mod_impl(op, exp, n)
+        } else {
+            let base = Self::module(op, n);
+            let mut res = Self::module(&Self::from(1), n);
+            for i in (0..exp.get_bit_count()).rev() {
+                res = Self::square_mod(&res, n);
+                if exp.get_bit(i) {
+                    res = Self::mul_mod(&res, &base, n);
+                }
+            }
+            Ok(res)
+        }
+    }
+
     pub fn relative_prime(op1: &Self, op2: &Self) -> bool {
         unsafe { raw::TEE_BigIntRelativePrime(op1.data_ptr(), op2.data_ptr()) }
     }
 
-    /* pub fn compute_extended_gcd(op1: &Self, op2: &Self) -> (Self, Self, Self)
-     * This function is implemented in OP-TEE, while the output size needs to be calculated based
-     * on the missing function TEE_BigIntAbs, so we do not port it yet.*/
+    /// Compute the greatest common divisor `gcd` of `op1` and `op2`, together with `u` and `v`
+    /// such that `u * op1 + v * op2 == gcd`. The result is returned as `(gcd, u, v)`.
+    pub fn extended_gcd(op1: &Self, op2: &Self) -> (Self, Self, Self) {
+        // Bit counts are those of the absolute values, and |u|, |v| are bounded by max(|op1|, |op2|).
+        let bits = max(Self::get_bit_count(op1), Self::get_bit_count(op2)) + 1;
+        let mut gcd = Self::new(bits);
+        let mut u = Self::new(bits);
+        let mut v = Self::new(bits);
+        unsafe {
+            raw::TEE_BigIntComputeExtendedGcd(
+                gcd.0.as_mut_ptr(),
+                u.0.as_mut_ptr(),
+                v.0.as_mut_ptr(),
+                op1.data_ptr(),
+                op2.data_ptr(),
+            )
+        };
+        (gcd, u, v)
+    }
 
     pub fn is_probable_prime(&self, confidence_level: u32) -> i32 {
         unsafe { raw::TEE_BigIntIsProbablePrime(self.data_ptr(), confidence_level) }
@@ -221,10 +305,10 @@ impl BigInt {
         &mut self,
         src: &BigIntFMM,
         n: &BigInt,
-        context: BigIntFMMContext,
+        context: &BigIntFMMContext,
     ) {
         unsafe {
-            raw::TEE_BigIntConvertToFMM(
+            raw::TEE_BigIntConvertFromFMM(
                 self.0.as_mut_ptr(),
                 src.data_ptr(),
                 n.data_ptr(),
@@ -234,9 +318,357 @@ impl BigInt {
     }
 }
 
+impl BigInt {
+    fn is_negative(&self) -> bool {
+        self.compare_s32(0) < 0
+    }
+
+    // Magnitude as little-endian u32 limbs, without leading zero limbs.
+    fn magnitude(&self) -> Vec<u32> {
+        // The buffer is sized for the allocated capacity, so `ShortBuffer` cannot happen.
+        let bytes = self
+            .convert_to_octet_string()
+            .expect("the octet string buffer holds the whole capacity");
+        let mut limbs: Vec<u32> = bytes
+            .rchunks(4)
+            .map(|c| c.iter().fold(0u32, |acc, &b| (acc << 8) | b as u32))
+            .collect();
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        limbs
+    }
+
+    fn from_magnitude(limbs: &[u32], negative: bool) -> Self {
+        let bytes = limbs_to_bytes(limbs);
+        let mut res = Self::new(max(32, bytes.len() as u32 * 8));
+        // The buffer always fits in the allocated size.
+        let _ = res.convert_from_octet_string(&bytes, if negative { -1 } else { 0 });
+        res
+    }
+}
+
+#[cfg(feature = "big_int_ext")]
+impl BigInt {
+    fn set_bit_impl(&mut self, bit_index: u32, value: bool) -> Result<()> {
+        match unsafe { raw::TEE_BigIntSetBit(self.0.as_mut_ptr(), bit_index, value) } {
+            raw::TEE_SUCCESS => Ok(()),
+            code => Err(Error::from_raw_error(code)),
+        }
+    }
+
+    fn assign_impl(&mut self, src: &Self) -> Result<()> {
+        match unsafe { raw::TEE_BigIntAssign(self.0.as_mut_ptr(), src.data_ptr()) } {
+            raw::TEE_SUCCESS => Ok(()),
+            code => Err(Error::from_raw_error(code)),
+        }
+    }
+
+    fn abs_impl(op: &Self) -> Self {
+        let mut res = Self::new(Self::get_bit_count(op));
+        // The result is allocated large enough to never overflow.
+        unsafe { raw::TEE_BigIntAbs(res.0.as_mut_ptr(), op.data_ptr()) };
+        res
+    }
+
+    fn exp_mod_impl(op: &Self, exp: &Self, n: &Self) -> Result<Self> {
+        let mut res = Self::new(Self::get_bit_count(n));
+        match unsafe {
+            raw::TEE_BigIntExpMod(
+                res.0.as_mut_ptr(),
+                op.data_ptr(),
+                exp.data_ptr(),
+                n.data_ptr(),
+                core::ptr::null(),
+            )
+        } {
+            raw::TEE_SUCCESS => Ok(res),
+            code => Err(Error::from_raw_error(code)),
+        }
+    }
+}
+
+#[cfg(not(feature = "big_int_ext"))]
+impl BigInt {
+    fn set_bit_impl(&mut self, bit_index: u32, value: bool) -> Result<()> {
+        if bit_index >= (self.0.len() as u32 - 2) * 32 {
+            return Err(Error::new(ErrorKind::Overflow));
+        }
+        let negative = self.is_negative();
+        let mut limbs = self.magnitude();
+        let index = (bit_index / 32) as usize;
+        if limbs.len() <= index {
+            limbs.resize(index + 1, 0);
+        }
+        if value {
+            limbs[index] |= 1 << (bit_index % 32);
+        } else {
+            limbs[index] &= !(1 << (bit_index % 32));
+        }
+        let bytes = limbs_to_bytes(&limbs);
+        let sign = if negative && limbs.iter().any(|&l| l != 0) {
+            -1
+        } else {
+            0
+        };
+        self.convert_from_octet_string(&bytes, sign)
+    }
+
+    fn assign_impl(&mut self, src: &Self) -> Result<()> {
+        let bytes = src.convert_to_octet_string()?;
+        let sign = if src.is_negative() { -1 } else { 0 };
+        self.convert_from_octet_string(&bytes, sign)
+    }
+
+    fn abs_impl(op: &Self) -> Self {
+        if op.is_negative() {
+            Self::neg(op)
+        } else {
+            op.clone()
+        }
+    }
+
+    // n has to be odd.
+    fn exp_mod_impl(op: &Self, exp: &Self, n: &Self) -> Result<Self> {
+        let mont = Montgomery::new(n)?;
+        let res = mont.pow(&mont.to_fmm(op), exp)?;
+        Ok(mont.from_fmm(&res))
+    }
+}
+
+impl From<i32> for BigInt {
+    fn from(value: i32) -> Self {
+        let mut res = Self::new(32);
+        res.convert_from_s32(value);
+        res
+    }
+}
+
+impl TryFrom<&BigInt> for i32 {
+    type Error = Error;
+
+    /// Fail with `Overflow` if the value does not fit in an `i32`.
+    fn try_from(value: &BigInt) -> Result<i32> {
+        value.convert_to_s32()
+    }
+}
+
+impl PartialEq for BigInt {
+    fn eq(&self, other: &Self) -> bool {
+        self.compare_big_int(other) == 0
+    }
+}
+
+impl Eq for BigInt {}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.compare_big_int(other).cmp(&0)
+    }
+}
+
+impl<'a, 'b> Add<&'b BigInt> for &'a BigInt {
+    type Output = BigInt;
+
+    fn add(self, rhs: &'b BigInt) -> BigInt {
+        BigInt::add(self, rhs)
+    }
+}
+
+impl<'a, 'b> Sub<&'b BigInt> for &'a BigInt {
+    type Output = BigInt;
+
+    fn sub(self, rhs: &'b BigInt) -> BigInt {
+        BigInt::sub(self, rhs)
+    }
+}
+
+impl<'a, 'b> Mul<&'b BigInt> for &'a BigInt {
+    type Output = BigInt;
+
+    fn mul(self, rhs: &'b BigInt) -> BigInt {
+        BigInt::multiply(self, rhs)
+    }
+}
+
+/// Truncated division, as for primitive integers. Panics if `rhs` is zero.
+impl<'a, 'b> Div<&'b BigInt> for &'a BigInt {
+    type Output = BigInt;
+
+    fn div(self, rhs: &'b BigInt) -> BigInt {
+        BigInt::divide(self, rhs).0
+    }
+}
+
+/// Remainder of the truncated division, with the sign of `self` as for primitive integers.
+/// Panics if `rhs` is zero. Use [module](BigInt::module) for the non-negative modular reduction.
+impl<'a, 'b> Rem<&'b BigInt> for &'a BigInt {
+    type Output = BigInt;
+
+    fn rem(self, rhs: &'b BigInt) -> BigInt {
+        BigInt::divide(self, rhs).1
+    }
+}
+
+impl<'a> Neg for &'a BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::neg(self)
+    }
+}
+
+impl<'a> Shr<u32> for &'a BigInt {
+    type Output = BigInt;
+
+    fn shr(self, bits: u32) -> BigInt {
+        let mut res = BigInt::new(max(32, self.get_bit_count()));
+        res.shift_right(self, bits as usize);
+        res
+    }
+}
+
+macro_rules! impl_op_assign {
+    ($trait:ident, $method:ident, $op:tt, $rhs:ty) => {
+        impl<'a> $trait<$rhs> for BigInt {
+            fn $method(&mut self, rhs: $rhs) {
+                *self = &*self $op rhs;
+            }
+        }
+    };
+}
+
+impl_op_assign!(AddAssign, add_assign, +, &'a BigInt);
+impl_op_assign!(SubAssign, sub_assign, -, &'a BigInt);
+impl_op_assign!(MulAssign, mul_assign, *, &'a BigInt);
+impl_op_assign!(DivAssign, div_assign, /, &'a BigInt);
+impl_op_assign!(RemAssign, rem_assign, %, &'a BigInt);
+impl_op_assign!(ShrAssign, shr_assign, >>, u32);
+
+/// Format the value in decimal.
 impl fmt::Display for BigInt {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:x?}", self.0)
+        let mut limbs = self.magnitude();
+        let mut chunks: Vec<u32> = Vec::new();
+        while !limbs.is_empty() {
+            chunks.push(div_rem_small(&mut limbs, 1_000_000_000));
+        }
+        let mut digits = match chunks.pop() {
+            Some(chunk) => chunk.to_string(),
+            None => "0".to_string(),
+        };
+        for chunk in chunks.iter().rev() {
+            digits.push_str(&format!("{:09}", chunk));
+        }
+        f.pad_integral(!self.is_negative(), "", &digits)
+    }
+}
+
+impl fmt::Debug for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "BigInt({})", self)
+    }
+}
+
+impl fmt::LowerHex for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad_integral(!self.is_negative(), "0x", &self.to_hex_digits())
+    }
+}
+
+impl fmt::UpperHex for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad_integral(
+            !self.is_negative(),
+            "0x",
+            &self.to_hex_digits().to_uppercase(),
+        )
+    }
+}
+
+impl BigInt {
+    fn to_hex_digits(&self) -> String {
+        let limbs = self.magnitude();
+        let mut digits = match limbs.last() {
+            Some(limb) => format!("{:x}", limb),
+            None => "0".to_string(),
+        };
+        for limb in limbs.iter().rev().skip(1) {
+            digits.push_str(&format!("{:08x}", limb));
+        }
+        digits
+    }
+}
+
+/// Parse a decimal number, or a hexadecimal number prefixed with `0x`, with an optional sign.
+impl FromStr for BigInt {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (negative, s) = if s.starts_with('-') {
+            (true, &s[1..])
+        } else if s.starts_with('+') {
+            (false, &s[1..])
+        } else {
+            (false, s)
+        };
+        let (radix, digits) = if s.starts_with("0x") || s.starts_with("0X") {
+            (16, &s[2..])
+        } else {
+            (10, s)
+        };
+        if digits.is_empty() {
+            return Err(Error::new(ErrorKind::BadFormat));
+        }
+        let mut limbs: Vec<u32> = Vec::new();
+        for c in digits.chars() {
+            match c.to_digit(radix) {
+                Some(d) => mul_add_small(&mut limbs, radix, d),
+                None => return Err(Error::new(ErrorKind::BadFormat)),
+            }
+        }
+        Ok(Self::from_magnitude(&limbs, negative))
+    }
+}
+
+fn limbs_to_bytes(limbs: &[u32]) -> Vec<u8> {
+    limbs
+        .iter()
+        .rev()
+        .flat_map(|l| l.to_be_bytes().to_vec())
+        .collect()
+}
+
+// Divide little-endian limbs in place by a small divisor and return the remainder.
+fn div_rem_small(limbs: &mut Vec<u32>, divisor: u32) -> u32 {
+    let mut rem: u64 = 0;
+    for limb in limbs.iter_mut().rev() {
+        let cur = (rem << 32) | *limb as u64;
+        *limb = (cur / divisor as u64) as u32;
+        rem = cur % divisor as u64;
+    }
+    while limbs.last() == Some(&0) {
+        limbs.pop();
+    }
+    rem as u32
+}
+
+// Compute `limbs * m + a` in place on little-endian limbs.
+fn mul_add_small(limbs: &mut Vec<u32>, m: u32, a: u32) {
+    let mut carry = a as u64;
+    for limb in limbs.iter_mut() {
+        let cur = *limb as u64 * m as u64 + carry;
+        *limb = cur as u32;
+        carry = cur >> 32;
+    }
+    if carry != 0 {
+        limbs.extend(iter::once(carry as u32));
     }
 }
 
@@ -252,7 +684,7 @@ impl BigIntFMMContext {
     }
 
     // Globalplatform define FMMContext1 here while OP-TEE does not update yet
-    pub fn new(bits: u32, modulus: BigInt) -> Result<Self> {
+    pub fn new(bits: u32, modulus: &BigInt) -> Result<Self> {
         let size: usize = Self::size_in_u32(bits) as usize;
         let mut tmp_vec: Vec<BigIntFMMContextUnit> = vec![0; size];
         unsafe {
@@ -281,7 +713,7 @@ impl BigIntFMM {
     }
 
     //Has to be initialized first
-    pub fn convert_from_big_int(&mut self, src: &BigInt, n: &BigInt, context: BigIntFMMContext) {
+    pub fn convert_from_big_int(&mut self, src: &BigInt, n: &BigInt, context: &BigIntFMMContext) {
         unsafe {
             raw::TEE_BigIntConvertToFMM(
                 self.0.as_mut_ptr(),
@@ -298,7 +730,7 @@ impl BigIntFMM {
         op1: &BigIntFMM,
         op2: &BigIntFMM,
         n: &BigInt,
-        context: BigIntFMMContext,
+        context: &BigIntFMMContext,
     ) {
         unsafe {
             raw::TEE_BigIntComputeFMM(
@@ -311,8 +743,96 @@ impl BigIntFMM {
         };
     }
 }
-//OP-TEE does not implement function:
-//TEE_BigIntSetBit
-//TEE_BigIntAssign
-//TEE_BigIntAbs
-//TEE_BigIntExpMod
+
+/// A fast modular multiplication context for an odd modulus.
+///
+/// Values are converted to their FMM representation with [to_fmm](Montgomery::to_fmm),
+/// multiplied and exponentiated in that representation and converted back with
+/// [from_fmm](Montgomery::from_fmm). The context owns a copy of the modulus and is only borrowed
+/// by its operations, so it can be reused for any number of them.
+///
+/// # Example
+///
+/// ```no_run
+/// let n: BigInt = "0xfffffffb".parse()?;
+/// let mont = Montgomery::new(&n)?;
+/// let x = mont.to_fmm(&BigInt::from(3));
+/// let y = mont.pow(&x, &BigInt::from(100))?;
+/// assert_eq!(mont.from_fmm(&y), BigInt::exp_mod(&BigInt::from(3), &BigInt::from(100), &n)?);
+/// ```
+pub struct Montgomery {
+    modulus: BigInt,
+    bits: u32,
+    context: BigIntFMMContext,
+}
+
+impl Montgomery {
+    /// Create a context for `modulus`.
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadParameters`: If `modulus` is even or not greater than 1.
+    pub fn new(modulus: &BigInt) -> Result<Self> {
+        if !modulus.get_bit(0) || modulus.compare_s32(1) <= 0 {
+            return Err(Error::new(ErrorKind::BadParameters));
+        }
+        let bits = modulus.get_bit_count();
+        let context = BigIntFMMContext::new(bits, modulus)?;
+        Ok(Self {
+            modulus: modulus.clone(),
+            bits,
+            context,
+        })
+    }
+
+    pub fn modulus(&self) -> &BigInt {
+        &self.modulus
+    }
+
+    /// Convert `op` to its FMM representation, `op` is reduced modulo the modulus first.
+    pub fn to_fmm(&self, op: &BigInt) -> BigIntFMM {
+        let reduced = BigInt::module(op, &self.modulus);
+        let mut res = BigIntFMM::new(self.bits);
+        res.convert_from_big_int(&reduced, &self.modulus, &self.context);
+        res
+    }
+
+    /// Convert `op` back from its FMM representation, the result is in `[0, modulus - 1]`.
+    pub fn from_fmm(&self, op: &BigIntFMM) -> BigInt {
+        let mut res = BigInt::new(self.bits);
+        res.convert_from_big_int_fmm(op, &self.modulus, &self.context);
+        res
+    }
+
+    pub fn mul(&self, op1: &BigIntFMM, op2: &BigIntFMM) -> BigIntFMM {
+        let mut res = BigIntFMM::new(self.bits);
+        res.compute_fmm(op1, op2, &self.modulus, &self.context);
+        res
+    }
+
+    pub fn square(&self, op: &BigIntFMM) -> BigIntFMM {
+        self.mul(op, op)
+    }
+
+    /// Compute `op ^ exp` with left-to-right square-and-multiply.
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadParameters`: If `exp` is negative.
+    pub fn pow(&self, op: &BigIntFMM, exp: &BigInt) -> Result<BigIntFMM> {
+        if exp.compare_s32(0) < 0 {
+            return Err(Error::new(ErrorKind::BadParameters));
+        }
+        let mut acc = self.to_fmm(&BigInt::from(1));
+        let mut tmp = BigIntFMM::new(self.bits);
+        for i in (0..exp.get_bit_count()).rev() {
+            tmp.compute_fmm(&acc, &acc, &self.modulus, &self.context);
+            core::mem::swap(&mut acc, &mut tmp);
+            if exp.get_bit(i) {
+                tmp.compute_fmm(&acc, op, &self.modulus, &self.context);
+                core::mem::swap(&mut acc, &mut tmp);
+            }
+        }
+        Ok(acc)
+    }
+}
@@ -0,0 +1,213 @@
+//! One-shot cryptographic functions.
+//!
+//! The functions in this module allocate the operations and key objects they need, run the whole
+//! computation in a single call and release everything before returning. Use the operations in
+//! [crypto_op](../crypto_op/index.html) directly for streaming data or for reusing a key across
+//! several calls.
+
+use crate::kdf::{Hmac, KdfHash};
+use crate::{
+    AlgorithmId, AttributeId, AttributeMemref, Digest, Error, ErrorKind, OperationMode, Random,
+    Result, TransientObject, TransientObjectType, AE,
+};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Length in bytes of the nonce generated by [aes_gcm_seal](aes_gcm_seal).
+pub const AES_GCM_NONCE_LEN: usize = 12;
+/// Length in bytes of the tag appended by [aes_gcm_seal](aes_gcm_seal).
+pub const AES_GCM_TAG_LEN: usize = 16;
+
+/// Compute the message digest of `data`.
+///
+/// # Parameters
+///
+/// 1) `algo`: One of the [Digest](../crypto_op/struct.Digest.html) supported algorithms, e.g.
+///    [Sha256](../crypto_op/enum.AlgorithmId.html#variant.Sha256).
+/// 2) `data`: The message.
+///
+/// # Example
+///
+/// ```no_run
+/// let hash = crypto::digest(AlgorithmId::Sha384, b"message")?;
+/// assert_eq!(hash.len(), 48);
+/// ```
+///
+/// # Errors
+///
+/// 1) `NotSupported`: If `algo` is not a digest algorithm.
+/// 2) `OutOfMemory`: If not enough resources are available to allocate the operation.
+pub fn digest(algo: AlgorithmId, data: &[u8]) -> Result<Vec<u8>> {
+    let mut hash = vec![0u8; 64];
+    let len = Digest::allocate(algo)?.do_final(data, &mut hash)?;
+    hash.truncate(len);
+    Ok(hash)
+}
+
+/// Compute the SHA-1 digest of `data`. Errors are the same as [digest](digest).
+pub fn sha1(data: &[u8]) -> Result<[u8; 20]> {
+    let mut hash = [0u8; 20];
+    Digest::allocate(AlgorithmId::Sha1)?.do_final(data, &mut hash)?;
+    Ok(hash)
+}
+
+/// Compute the SHA-224 digest of `data`. Errors are the same as [digest](digest).
+pub fn sha224(data: &[u8]) -> Result<[u8; 28]> {
+    let mut hash = [0u8; 28];
+    Digest::allocate(AlgorithmId::Sha224)?.do_final(data, &mut hash)?;
+    Ok(hash)
+}
+
+/// Compute the SHA-256 digest of `data`. Errors are the same as [digest](digest).
+///
+/// # Example
+///
+/// ```no_run
+/// let hash: [u8; 32] = crypto::sha256(b"message")?;
+/// ```
+pub fn sha256(data: &[u8]) -> Result<[u8; 32]> {
+    let mut hash = [0u8; 32];
+    Digest::allocate(AlgorithmId::Sha256)?.do_final(data, &mut hash)?;
+    Ok(hash)
+}
+
+/// Compute the SHA-384 digest of `data`. Errors are the same as [digest](digest).
+pub fn sha384(data: &[u8]) -> Result<[u8; 48]> {
+    let mut hash = [0u8; 48];
+    Digest::allocate(AlgorithmId::Sha384)?.do_final(data, &mut hash)?;
+    Ok(hash)
+}
+
+/// Compute the SHA-512 digest of `data`. Errors are the same as [digest](digest).
+pub fn sha512(data: &[u8]) -> Result<[u8; 64]> {
+    let mut hash = [0u8; 64];
+    Digest::allocate(AlgorithmId::Sha512)?.do_final(data, &mut hash)?;
+    Ok(hash)
+}
+
+/// Compute the HMAC of `data`.
+///
+/// Unlike a [Mac](../crypto_op/struct.Mac.html) operation keyed with an HMAC object, the key can
+/// have any length: keys longer than the block size of the hash function are hashed first, as
+/// specified by RFC 2104.
+///
+/// # Parameters
+///
+/// 1) `algo`: One of [HmacSha1](../crypto_op/enum.AlgorithmId.html#variant.HmacSha1),
+///    `HmacSha224`, `HmacSha256`, `HmacSha384` or `HmacSha512`.
+/// 2) `key`: The secret key.
+/// 3) `data`: The message.
+///
+/// # Example
+///
+/// ```no_run
+/// let mac = crypto::hmac(AlgorithmId::HmacSha256, b"key", b"message")?;
+/// crypto::hmac_verify(AlgorithmId::HmacSha256, b"key", b"message", &mac)?;
+/// ```
+///
+/// # Errors
+///
+/// 1) `NotSupported`: If `algo` is not one of the algorithms above.
+/// 2) `OutOfMemory`: If not enough resources are available to allocate the operation.
+pub fn hmac(algo: AlgorithmId, key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let hash = hmac_hash(algo)?;
+    let mut mac = vec![0u8; hash.output_len()];
+    Hmac::new(hash, key)?.compute(&[data], &mut mac)?;
+    Ok(mac)
+}
+
+/// Compute the HMAC-SHA256 of `data`. Errors are the same as [hmac](hmac).
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<[u8; 32]> {
+    let mut mac = [0u8; 32];
+    Hmac::new(KdfHash::Sha256, key)?.compute(&[data], &mut mac)?;
+    Ok(mac)
+}
+
+/// Check the HMAC of `data` against `mac`, the comparison is done by the implementation in
+/// constant time.
+///
+/// # Errors
+///
+/// 1) `MacInvalid`: If the computed MAC does not correspond to `mac`.
+/// 2) Other errors are the same as [hmac](hmac).
+pub fn hmac_verify(algo: AlgorithmId, key: &[u8], data: &[u8], mac: &[u8]) -> Result<()> {
+    Hmac::new(hmac_hash(algo)?, key)?.verify(&[data], mac)
+}
+
+fn hmac_hash(algo: AlgorithmId) -> Result<KdfHash> {
+    match KdfHash::from_hmac_algorithm(algo) {
+        Some(hash) => Ok(hash),
+        None => Err(Error::new(ErrorKind::NotSupported)),
+    }
+}
+
+/// Encrypt and authenticate `plaintext` with AES-GCM under a freshly generated random nonce.
+///
+/// The returned sealed box is `nonce || ciphertext || tag`, with a
+/// [AES_GCM_NONCE_LEN](AES_GCM_NONCE_LEN) bytes nonce and a [AES_GCM_TAG_LEN](AES_GCM_TAG_LEN)
+/// bytes tag, and can be opened with [aes_gcm_open](aes_gcm_open).
+///
+/// # Parameters
+///
+/// 1) `key`: AES key of 16, 24 or 32 bytes.
+/// 2) `aad`: Additional authenticated data, can be empty.
+/// 3) `plaintext`: Data to encrypt.
+///
+/// # Example
+///
+/// ```no_run
+/// let key = [0xa5u8; 32];
+/// let sealed = crypto::aes_gcm_seal(&key, b"header", b"secret data")?;
+/// let opened = crypto::aes_gcm_open(&key, b"header", &sealed)?;
+/// assert_eq!(&opened[..], b"secret data");
+/// ```
+///
+/// # Errors
+///
+/// 1) `BadParameters`: If the key length is not valid for AES.
+/// 2) `OutOfMemory`: If not enough resources are available to allocate the operation.
+pub fn aes_gcm_seal(key: &[u8], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    let op = aes_gcm_operation(key, OperationMode::Encrypt)?;
+    let mut sealed = vec![0u8; AES_GCM_NONCE_LEN + plaintext.len() + AES_GCM_TAG_LEN];
+    let (nonce, rest) = sealed.split_at_mut(AES_GCM_NONCE_LEN);
+    let (ciphertext, tag) = rest.split_at_mut(plaintext.len());
+    Random::generate(nonce);
+    op.init(nonce, AES_GCM_TAG_LEN * 8, 0, 0)?;
+    op.update_aad(aad);
+    op.encrypt_final(plaintext, ciphertext, tag)?;
+    Ok(sealed)
+}
+
+/// Authenticate and decrypt a sealed box produced by [aes_gcm_seal](aes_gcm_seal).
+///
+/// # Errors
+///
+/// 1) `BadFormat`: If `sealed` is too short to contain a nonce and a tag.
+/// 2) `MacInvalid`: If the sealed box or `aad` has been tampered with, or the key is wrong.
+/// 3) Other errors are the same as [aes_gcm_seal](aes_gcm_seal).
+pub fn aes_gcm_open(key: &[u8], aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>> {
+    if sealed.len() < AES_GCM_NONCE_LEN + AES_GCM_TAG_LEN {
+        return Err(Error::new(ErrorKind::BadFormat));
+    }
+    let op = aes_gcm_operation(key, OperationMode::Decrypt)?;
+    let (nonce, rest) = sealed.split_at(AES_GCM_NONCE_LEN);
+    let (ciphertext, tag) = rest.split_at(rest.len() - AES_GCM_TAG_LEN);
+    let mut plaintext = vec![0u8; ciphertext.len()];
+    op.init(nonce, AES_GCM_TAG_LEN * 8, 0, 0)?;
+    op.update_aad(aad);
+    let len = op.decrypt_final(ciphertext, &mut plaintext, tag)?;
+    plaintext.truncate(len);
+    Ok(plaintext)
+}
+
+fn aes_gcm_operation(key: &[u8], mode: OperationMode) -> Result<AE> {
+    match key.len() {
+        16 | 24 | 32 => (),
+        _ => return Err(Error::new(ErrorKind::BadParameters)),
+    }
+    let mut key_object = TransientObject::allocate(TransientObjectType::Aes, key.len() * 8)?;
+    key_object.populate(&[AttributeMemref::from_ref(AttributeId::SecretValue, key).into()])?;
+    let op = AE::allocate(AlgorithmId::AesGcm, mode, key.len() * 8)?;
+    op.set_key(&key_object)?;
+    Ok(op)
+}
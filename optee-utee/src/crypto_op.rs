@@ -1,8 +1,15 @@
-use crate::{Attribute, Error, ObjHandle, Result, TransientObject};
+use crate::{
+    Attribute, Error, ErrorKind, HandleFlag, ObjHandle, Result, SecretVec, TransientObject,
+    UsageFlag,
+};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::{mem, ptr};
 use optee_utee_sys as raw;
-use std::{mem, ptr};
 
 /// Specify one of the available cryptographic operations.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[repr(u32)]
 pub enum OperationMode {
     /// Encryption mode
@@ -23,6 +30,23 @@ pub enum OperationMode {
     IllegalValue = 0x7fffffff,
 }
 
+impl OperationMode {
+    /// Return the mode with the raw value `value`, or `None` if it is unknown.
+    pub fn from_raw(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(OperationMode::Encrypt),
+            1 => Some(OperationMode::Decrypt),
+            2 => Some(OperationMode::Sign),
+            3 => Some(OperationMode::Verify),
+            4 => Some(OperationMode::Mac),
+            5 => Some(OperationMode::Digest),
+            6 => Some(OperationMode::Derive),
+            0x7fffffff => Some(OperationMode::IllegalValue),
+            _ => None,
+        }
+    }
+}
+
 /// Represent the information about a crypto information.
 pub struct OperationInfo {
     raw: raw::TEE_OperationInfo,
@@ -56,6 +80,11 @@ impl OperationInfo {
         Self { raw }
     }
 
+    /// Return the raw structure `TEE_OperationInfo`.
+    pub fn raw(&self) -> &raw::TEE_OperationInfo {
+        &self.raw
+    }
+
     /// Return the `keySize` field of the raw structure `TEE_OperationInfo`.
     pub fn key_size(&self) -> u32 {
         self.raw.keySize
@@ -65,10 +94,55 @@ impl OperationInfo {
     pub fn max_key_size(&self) -> u32 {
         self.raw.maxKeySize
     }
+
+    /// Return the `algorithm` field of the raw structure `TEE_OperationInfo`, or `None` if it is
+    /// not one of the [AlgorithmId](AlgorithmId).
+    pub fn algorithm(&self) -> Option<AlgorithmId> {
+        AlgorithmId::from_raw(self.raw.algorithm)
+    }
+
+    /// Return the `operationClass` field of the raw structure `TEE_OperationInfo`, or `None` if it
+    /// is not one of the [OperationConstant](OperationConstant).
+    pub fn operation_class(&self) -> Option<OperationConstant> {
+        OperationConstant::from_raw(self.raw.operationClass)
+    }
+
+    /// Return the `mode` field of the raw structure `TEE_OperationInfo`, or `None` if it is not
+    /// one of the [OperationMode](OperationMode).
+    pub fn mode(&self) -> Option<OperationMode> {
+        OperationMode::from_raw(self.raw.mode)
+    }
+
+    /// Return the `digestLength` field of the raw structure `TEE_OperationInfo`.
+    pub fn digest_length(&self) -> u32 {
+        self.raw.digestLength
+    }
+
+    /// Return the `requiredKeyUsage` field of the raw structure `TEE_OperationInfo`.
+    pub fn required_key_usage(&self) -> UsageFlag {
+        UsageFlag::from_bits_truncate(self.raw.requiredKeyUsage)
+    }
+
+    /// Return the `handleState` field of the raw structure `TEE_OperationInfo`.
+    pub fn handle_state(&self) -> HandleFlag {
+        HandleFlag::from_bits_truncate(self.raw.handleState)
+    }
+
+    /// Return true if the key required by the operation has been set, always true for a
+    /// [Digest](Digest).
+    pub fn has_key(&self) -> bool {
+        self.handle_state().contains(HandleFlag::KEY_SET)
+    }
+
+    /// Return true if the algorithm expects two keys, set with [Cipher::set_key_2](Cipher::set_key_2).
+    pub fn expects_two_keys(&self) -> bool {
+        self.handle_state().contains(HandleFlag::EXPECT_TWO_KEYS)
+    }
 }
 
 /// Every operation of [AE](AE), [Asymmetric](Asymmetric), [Cipher](Cipher),
 /// [DeriveKey](DeriveKey), [Digest](Digest), [Mac](Mac) can be either one of the two states.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[repr(u32)]
 pub enum OperationStates {
     /// Nothing is going on.
@@ -78,6 +152,7 @@ pub enum OperationStates {
 }
 
 /// Define the supported crypto operation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum OperationConstant {
     /// [Cipher](Cipher)
     Cipher = 1,
@@ -95,6 +170,22 @@ pub enum OperationConstant {
     KeyDerivation = 8,
 }
 
+impl OperationConstant {
+    /// Return the operation class with the raw value `value`, or `None` if it is unknown.
+    pub fn from_raw(value: u32) -> Option<Self> {
+        match value {
+            raw::TEE_OPERATION_CIPHER => Some(OperationConstant::Cipher),
+            raw::TEE_OPERATION_MAC => Some(OperationConstant::Mac),
+            raw::TEE_OPERATION_AE => Some(OperationConstant::Ae),
+            raw::TEE_OPERATION_DIGEST => Some(OperationConstant::Digest),
+            raw::TEE_OPERATION_ASYMMETRIC_CIPHER => Some(OperationConstant::AsymmetricCipher),
+            raw::TEE_OPERATION_ASYMMETRIC_SIGNATURE => Some(OperationConstant::AsymmetricSignature),
+            raw::TEE_OPERATION_KEY_DERIVATION => Some(OperationConstant::KeyDerivation),
+            _ => None,
+        }
+    }
+}
+
 /// Represent the information about a crypto information which uses multiple keys.
 pub struct OperationInfoMultiple {
     raw: *mut raw::TEE_OperationInfoMultiple,
@@ -135,6 +226,14 @@ impl OperationInfoMultiple {
     pub fn size(&self) -> usize {
         self.size
     }
+
+    /// Return the `operationState` field of the raw structure `TEE_OperationInfoMultiple`.
+    pub fn operation_state(&self) -> OperationStates {
+        match unsafe { (*self.raw).operationState } {
+            raw::TEE_OPERATION_STATE_ACTIVE => OperationStates::Active,
+            _ => OperationStates::Initial,
+        }
+    }
 }
 
 /// An opaque reference that identifies a particular cryptographic operation.
@@ -155,6 +254,10 @@ impl OperationHandle {
         OperationHandle::from_raw(ptr::null_mut())
     }
 
+    fn is_null(&self) -> bool {
+        self.raw.is_null()
+    }
+
     fn allocate(algo: AlgorithmId, mode: OperationMode, max_key_size: usize) -> Result<Self> {
         let raw_handle: *mut raw::TEE_OperationHandle = Box::into_raw(Box::new(ptr::null_mut()));
         match unsafe {
@@ -253,6 +356,30 @@ impl Digest {
         }
     }
 
+    /// Accumulate message data for hashing like [update](Digest::update), but return the errors
+    /// which can be detected beforehand instead of panicking, since `TEE_DigestUpdate` does not
+    /// return any.
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadState`: If the operation is [null](Digest::null).
+    /// 2) `BadParameters`: If `chunk` is longer than `u32::max_value()` bytes.
+    ///
+    /// # Panics
+    ///
+    /// 1) Hardware or cryptographic algorithm failure.
+    /// 2) If the Implementation detects any other error.
+    pub fn try_update(&self, chunk: &[u8]) -> Result<()> {
+        if self.0.is_null() {
+            return Err(Error::new(ErrorKind::BadState));
+        }
+        if chunk.len() > u32::max_value() as usize {
+            return Err(Error::new(ErrorKind::BadParameters));
+        }
+        self.update(chunk);
+        Ok(())
+    }
+
     /// Finalize the message digest operation and produces the message hash. Afterwards the
     /// Message Digest operation is reset to initial state and can be reused.
     ///
@@ -761,6 +888,31 @@ impl Mac {
     pub fn update(&self, chunk: &[u8]) {
         unsafe { raw::TEE_MACUpdate(self.handle(), chunk.as_ptr() as _, chunk.len() as u32) };
     }
+
+    /// Accumulate data for a MAC calculation like [update](Mac::update), but return the errors
+    /// which can be detected beforehand instead of panicking, since `TEE_MACUpdate` does not
+    /// return any.
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadState`: If the operation is [null](Mac::null) or has not been initialized with
+    ///    [init](Mac::init).
+    /// 2) `BadParameters`: If `chunk` is longer than `u32::max_value()` bytes.
+    ///
+    /// # Panics
+    ///
+    /// 1) Hardware or cryptographic algorithm failure.
+    /// 2) If the Implementation detects any other error.
+    pub fn try_update(&self, chunk: &[u8]) -> Result<()> {
+        if self.0.is_null() || !self.info().handle_state().contains(HandleFlag::INITIALIZED) {
+            return Err(Error::new(ErrorKind::BadState));
+        }
+        if chunk.len() > u32::max_value() as usize {
+            return Err(Error::new(ErrorKind::BadParameters));
+        }
+        self.update(chunk);
+        Ok(())
+    }
     /// Finalize the MAC operation with a last chunk of message, and computes the MAC.
     /// Afterwards the operation handle can be reused or re-initialized with a new key.
     /// The operation SHALL be in active state and moves to initial state afterwards.
@@ -1240,28 +1392,24 @@ impl Asymmetric {
     /// 2) If no key is programmed in the operation.
     /// 3) Hardware or cryptographic algorithm failure.
     /// 4) If the Implementation detects any other error.
-    // This function can update output size with short buffer error when buffer is too
-    // short, and example acipher utilizes this feature!
-    // Define this function as unsafe because we need to return Ok for short buffer error.
     pub fn encrypt(&self, params: &[Attribute], src: &[u8]) -> Result<Vec<u8>> {
-        let p: Vec<raw::TEE_Attribute> = params.iter().map(|p| p.raw()).collect();
-        let mut res_size: u32 = self.info().key_size();
-        let mut res_vec: Vec<u8> = vec![0u8; res_size as usize];
-        match unsafe {
-            raw::TEE_AsymmetricEncrypt(
-                self.handle(),
-                p.as_ptr() as _,
-                params.len() as u32,
-                src.as_ptr() as _,
-                src.len() as u32,
-                res_vec.as_mut_ptr() as _,
-                &mut res_size,
-            )
-        } {
-            raw::TEE_SUCCESS => {
-                res_vec.truncate(res_size as usize);
-                return Ok(res_vec);
-            }
+        self.crypt_to_vec(true, params, src)
+    }
+
+    /// Encrypt a message into a caller provided buffer and return the number of bytes written.
+    /// Use [output_len_for](Asymmetric::output_len_for) to size `dest`.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `params`: Optional operation parameters.
+    /// 2) `src`: Input plaintext buffer.
+    /// 3) `dest`: Output buffer written with the ciphertext.
+    ///
+    /// Errors and panics are the same as [encrypt](Asymmetric::encrypt).
+    pub fn encrypt_into(&self, params: &[Attribute], src: &[u8], dest: &mut [u8]) -> Result<usize> {
+        let mut dest_size = dest.len() as u32;
+        match self.crypt(true, params, src, dest.as_mut_ptr(), &mut dest_size) {
+            raw::TEE_SUCCESS => Ok(dest_size as usize),
             code => Err(Error::from_raw_error(code)),
         }
     }
@@ -1287,25 +1435,70 @@ impl Asymmetric {
     /// 3) Hardware or cryptographic algorithm failure.
     /// 4) If the Implementation detects any other error.
     pub fn decrypt(&self, params: &[Attribute], src: &[u8]) -> Result<Vec<u8>> {
-        let p: Vec<raw::TEE_Attribute> = params.iter().map(|p| p.raw()).collect();
-        let mut res_size: u32 = self.info().key_size();
+        self.crypt_to_vec(false, params, src)
+    }
+
+    /// Decrypt a message into a caller provided buffer and return the number of bytes written.
+    /// Use [output_len_for](Asymmetric::output_len_for) to size `dest`.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `params`: Optional operation parameters.
+    /// 2) `src`: Input ciphertext buffer.
+    /// 3) `dest`: Output buffer written with the plaintext.
+    ///
+    /// Errors and panics are the same as [decrypt](Asymmetric::decrypt).
+    pub fn decrypt_into(&self, params: &[Attribute], src: &[u8], dest: &mut [u8]) -> Result<usize> {
+        let mut dest_size = dest.len() as u32;
+        match self.crypt(false, params, src, dest.as_mut_ptr(), &mut dest_size) {
+            raw::TEE_SUCCESS => Ok(dest_size as usize),
+            code => Err(Error::from_raw_error(code)),
+        }
+    }
+
+    // Query the output size with a null buffer first, the implementation then returns
+    // `ShortBuffer` along with the required size.
+    fn crypt_to_vec(&self, encrypt: bool, params: &[Attribute], src: &[u8]) -> Result<Vec<u8>> {
+        let mut res_size: u32 = 0;
+        match self.crypt(encrypt, params, src, ptr::null_mut(), &mut res_size) {
+            raw::TEE_SUCCESS => return Ok(Vec::new()),
+            raw::TEE_ERROR_SHORT_BUFFER => (),
+            code => return Err(Error::from_raw_error(code)),
+        }
         let mut res_vec: Vec<u8> = vec![0u8; res_size as usize];
-        match unsafe {
-            raw::TEE_AsymmetricDecrypt(
+        match self.crypt(encrypt, params, src, res_vec.as_mut_ptr(), &mut res_size) {
+            raw::TEE_SUCCESS => {
+                res_vec.truncate(res_size as usize);
+                Ok(res_vec)
+            }
+            code => Err(Error::from_raw_error(code)),
+        }
+    }
+
+    fn crypt(
+        &self,
+        encrypt: bool,
+        params: &[Attribute],
+        src: &[u8],
+        dest: *mut u8,
+        dest_size: &mut u32,
+    ) -> raw::TEE_Result {
+        let p: Vec<raw::TEE_Attribute> = params.iter().map(|p| p.raw()).collect();
+        let f = if encrypt {
+            raw::TEE_AsymmetricEncrypt
+        } else {
+            raw::TEE_AsymmetricDecrypt
+        };
+        unsafe {
+            f(
                 self.handle(),
                 p.as_ptr() as _,
                 params.len() as u32,
                 src.as_ptr() as _,
                 src.len() as u32,
-                res_vec.as_mut_ptr() as _,
-                &mut res_size,
+                dest as _,
+                dest_size,
             )
-        } {
-            raw::TEE_SUCCESS => {
-                res_vec.truncate(res_size as usize);
-                return Ok(res_vec);
-            }
-            code => Err(Error::from_raw_error(code)),
         }
     }
 
@@ -1400,6 +1593,122 @@ impl Asymmetric {
         }
     }
 
+    /// Sign a message: the message is hashed with the digest algorithm matching the operation
+    /// algorithm, e.g. SHA-256 for [RsassaPkcs1V15Sha256](AlgorithmId::RsassaPkcs1V15Sha256),
+    /// and the digest is signed with [sign_digest](Asymmetric::sign_digest).
+    ///
+    /// # Parameters
+    ///
+    /// 1) `params`: Optional operation parameters.
+    /// 2) `message`: Input buffer containing the message.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let operation = Asymmetric::allocate(AlgorithmId::RsassaPkcs1V15Sha256,
+    ///     OperationMode::Sign, 2048)?;
+    /// operation.set_key(&key_pair)?;
+    /// let signature = operation.sign_message(&[], b"message")?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) `NotSupported`: If the algorithm does not determine a digest algorithm, e.g.
+    ///    [RsaNopad](AlgorithmId::RsaNopad).
+    ///
+    /// Panics are the same as [sign_digest](Asymmetric::sign_digest).
+    pub fn sign_message(&self, params: &[Attribute], message: &[u8]) -> Result<Vec<u8>> {
+        let mut signature = vec![0u8; self.output_len_for(message.len())];
+        let len = self.sign_message_into(params, message, &mut signature)?;
+        signature.truncate(len);
+        Ok(signature)
+    }
+
+    /// Same as [sign_message](Asymmetric::sign_message) but write the signature into a caller
+    /// provided buffer and return its length.
+    ///
+    /// # Errors
+    ///
+    /// 1) `ShortBuffer`: If `signature` is not large enough to hold the result.
+    /// 2) `NotSupported`: If the algorithm does not determine a digest algorithm.
+    pub fn sign_message_into(
+        &self,
+        params: &[Attribute],
+        message: &[u8],
+        signature: &mut [u8],
+    ) -> Result<usize> {
+        let mut digest = [0u8; 64];
+        let digest_len = self.digest_message(message, &mut digest)?;
+        self.sign_digest(params, &digest[..digest_len], signature)
+    }
+
+    /// Verify the signature of a message, hashing it as [sign_message](Asymmetric::sign_message)
+    /// does.
+    ///
+    /// # Errors
+    ///
+    /// 1) `SignatureInvalid`: If the signature is invalid.
+    /// 2) `NotSupported`: If the algorithm does not determine a digest algorithm.
+    ///
+    /// Panics are the same as [verify_digest](Asymmetric::verify_digest).
+    pub fn verify_message(
+        &self,
+        params: &[Attribute],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<()> {
+        let mut digest = [0u8; 64];
+        let digest_len = self.digest_message(message, &mut digest)?;
+        self.verify_digest(params, &digest[..digest_len], signature)
+    }
+
+    /// Return the size of the output buffer needed for an input of `input_len` bytes, based on
+    /// the mode, algorithm and key size of the operation. The value is an upper bound, the
+    /// functions writing into caller buffers return the actual length.
+    ///
+    /// 1) [Encrypt](OperationMode::Encrypt): The modulus size.
+    /// 2) [Decrypt](OperationMode::Decrypt): `input_len`, the plaintext is never longer than the
+    ///    ciphertext.
+    /// 3) [Sign](OperationMode::Sign): The signature size, i.e. the modulus size for RSA, and
+    ///    twice the subprime or curve size for DSA and ECDSA.
+    /// 4) [Verify](OperationMode::Verify): 0.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let mut ciphertext = vec![0u8; operation.output_len_for(plaintext.len())];
+    /// let len = operation.encrypt_into(&[], &plaintext, &mut ciphertext)?;
+    /// ciphertext.truncate(len);
+    /// ```
+    pub fn output_len_for(&self, input_len: usize) -> usize {
+        let info = self.info();
+        let key_len = (info.key_size() as usize + 7) / 8;
+        match info.mode() {
+            Some(OperationMode::Decrypt) => input_len,
+            Some(OperationMode::Verify) => 0,
+            Some(OperationMode::Sign) => match info.algorithm() {
+                // DSA: the subprime size is fixed by the digest algorithm.
+                Some(AlgorithmId::DSASha1) => 2 * 20,
+                Some(AlgorithmId::DSASha224) => 2 * 28,
+                Some(AlgorithmId::DSASha256) => 2 * 32,
+                Some(AlgorithmId::EcdsaP192)
+                | Some(AlgorithmId::EcdsaP224)
+                | Some(AlgorithmId::EcdsaP256)
+                | Some(AlgorithmId::EcdsaP384)
+                | Some(AlgorithmId::EcdsaP521) => 2 * key_len,
+                _ => key_len,
+            },
+            _ => key_len,
+        }
+    }
+
+    fn digest_message(&self, message: &[u8], digest: &mut [u8]) -> Result<usize> {
+        match self.info().algorithm().and_then(digest_algorithm_for) {
+            Some(algo) => Digest::allocate(algo)?.do_final(message, digest),
+            None => Err(Error::new(ErrorKind::NotSupported)),
+        }
+    }
+
     /// Create an Asymmetric operation without any specific algorithm or other data.
     pub fn null() -> Self {
         Self(OperationHandle::null())
@@ -1440,6 +1749,33 @@ impl OpHandle for Asymmetric {
     }
 }
 
+// The digest algorithm of a signature algorithm, chosen according to the curve size for ECDSA.
+fn digest_algorithm_for(algo: AlgorithmId) -> Option<AlgorithmId> {
+    match algo {
+        AlgorithmId::RsassaPkcs1V15MD5 => Some(AlgorithmId::Md5),
+        AlgorithmId::RsassaPkcs1V15Sha1
+        | AlgorithmId::RsassaPkcs1PssMgf1Sha1
+        | AlgorithmId::DSASha1
+        | AlgorithmId::EcdsaP192 => Some(AlgorithmId::Sha1),
+        AlgorithmId::RsassaPkcs1V15Sha224
+        | AlgorithmId::RsassaPkcs1PssMgf1Sha224
+        | AlgorithmId::DSASha224
+        | AlgorithmId::EcdsaP224 => Some(AlgorithmId::Sha224),
+        AlgorithmId::RsassaPkcs1V15Sha256
+        | AlgorithmId::RsassaPkcs1PssMgf1Sha256
+        | AlgorithmId::DSASha256
+        | AlgorithmId::EcdsaP256 => Some(AlgorithmId::Sha256),
+        AlgorithmId::RsassaPkcs1V15Sha384
+        | AlgorithmId::RsassaPkcs1PssMgf1Sha384
+        | AlgorithmId::EcdsaP384 => Some(AlgorithmId::Sha384),
+        AlgorithmId::RsassaPkcs1V15Sha512
+        | AlgorithmId::RsassaPkcs1PssMgf1Sha512
+        | AlgorithmId::EcdsaP521 => Some(AlgorithmId::Sha512),
+        AlgorithmId::RsassaPkcs1V15MD5Sha1 => Some(AlgorithmId::Md5Sha1),
+        _ => None,
+    }
+}
+
 /// An operation for derive a shared key object.
 pub struct DeriveKey(OperationHandle);
 
@@ -1582,9 +1918,30 @@ impl Random {
             raw::TEE_GenerateRandom(res_buffer.as_mut_ptr() as _, res_buffer.len() as _);
         }
     }
+
+    /// Generate `len` bytes of random key material in a [SecretVec](../secret/type.SecretVec.html),
+    /// which is wiped when it is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let key = Random::generate_secret(32);
+    /// let attr = AttributeMemref::from_ref(AttributeId::SecretValue, key.expose());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// 1) Hardware or cryptographic algorithm failure.
+    /// 2) If the Implementation detects any other error.
+    pub fn generate_secret(len: usize) -> SecretVec {
+        let mut secret = SecretVec::zeroed(len);
+        Self::generate(secret.expose_mut());
+        secret
+    }
 }
 
 /// Algorithms that can be allocated as an crypto operation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[repr(u32)]
 pub enum AlgorithmId {
     /// [Cipher](Cipher) supported algorithm.
@@ -1691,6 +2048,31 @@ pub enum AlgorithmId {
     DSASha256 = 0x70004131,
     /// [DeriveKey](DeriveKey) supported algorithm.
     DhDeriveSharedSecret = 0x80000032,
+    /// [Asymmetric](Asymmetric) supported algorithm, can be applied with
+    /// [Sign](OperationMode::Sign) or [Verify](OperationMode::Verify) mode.
+    EcdsaP192 = 0x70001041,
+    /// [Asymmetric](Asymmetric) supported algorithm, can be applied with
+    /// [Sign](OperationMode::Sign) or [Verify](OperationMode::Verify) mode.
+    EcdsaP224 = 0x70002041,
+    /// [Asymmetric](Asymmetric) supported algorithm, can be applied with
+    /// [Sign](OperationMode::Sign) or [Verify](OperationMode::Verify) mode.
+    EcdsaP256 = 0x70003041,
+    /// [Asymmetric](Asymmetric) supported algorithm, can be applied with
+    /// [Sign](OperationMode::Sign) or [Verify](OperationMode::Verify) mode.
+    EcdsaP384 = 0x70004041,
+    /// [Asymmetric](Asymmetric) supported algorithm, can be applied with
+    /// [Sign](OperationMode::Sign) or [Verify](OperationMode::Verify) mode.
+    EcdsaP521 = 0x70005041,
+    /// [DeriveKey](DeriveKey) supported algorithm.
+    EcdhP192 = 0x80001042,
+    /// [DeriveKey](DeriveKey) supported algorithm.
+    EcdhP224 = 0x80002042,
+    /// [DeriveKey](DeriveKey) supported algorithm.
+    EcdhP256 = 0x80003042,
+    /// [DeriveKey](DeriveKey) supported algorithm.
+    EcdhP384 = 0x80004042,
+    /// [DeriveKey](DeriveKey) supported algorithm.
+    EcdhP521 = 0x80005042,
     /// [Digest](Digest) supported algorithm.
     Md5 = 0x50000001,
     /// [Digest](Digest) supported algorithm.
@@ -1717,13 +2099,149 @@ pub enum AlgorithmId {
     HmacSha384 = 0x30000005,
     /// [Mac](Mac) supported algorithm.
     HmacSha512 = 0x30000006,
+    /// [DeriveKey](DeriveKey) supported algorithm (OP-TEE extension, see [kdf](../kdf/index.html)).
+    HkdfMd5DeriveKey = 0x800010C0,
+    /// [DeriveKey](DeriveKey) supported algorithm (OP-TEE extension, see [kdf](../kdf/index.html)).
+    HkdfSha1DeriveKey = 0x800020C0,
+    /// [DeriveKey](DeriveKey) supported algorithm (OP-TEE extension, see [kdf](../kdf/index.html)).
+    HkdfSha224DeriveKey = 0x800030C0,
+    /// [DeriveKey](DeriveKey) supported algorithm (OP-TEE extension, see [kdf](../kdf/index.html)).
+    HkdfSha256DeriveKey = 0x800040C0,
+    /// [DeriveKey](DeriveKey) supported algorithm (OP-TEE extension, see [kdf](../kdf/index.html)).
+    HkdfSha384DeriveKey = 0x800050C0,
+    /// [DeriveKey](DeriveKey) supported algorithm (OP-TEE extension, see [kdf](../kdf/index.html)).
+    HkdfSha512DeriveKey = 0x800060C0,
+    /// [DeriveKey](DeriveKey) supported algorithm (OP-TEE extension, see [kdf](../kdf/index.html)).
+    ConcatKdfSha1DeriveKey = 0x800020C1,
+    /// [DeriveKey](DeriveKey) supported algorithm (OP-TEE extension, see [kdf](../kdf/index.html)).
+    ConcatKdfSha224DeriveKey = 0x800030C1,
+    /// [DeriveKey](DeriveKey) supported algorithm (OP-TEE extension, see [kdf](../kdf/index.html)).
+    ConcatKdfSha256DeriveKey = 0x800040C1,
+    /// [DeriveKey](DeriveKey) supported algorithm (OP-TEE extension, see [kdf](../kdf/index.html)).
+    ConcatKdfSha384DeriveKey = 0x800050C1,
+    /// [DeriveKey](DeriveKey) supported algorithm (OP-TEE extension, see [kdf](../kdf/index.html)).
+    ConcatKdfSha512DeriveKey = 0x800060C1,
+    /// [DeriveKey](DeriveKey) supported algorithm (OP-TEE extension, see [kdf](../kdf/index.html)).
+    Pbkdf2HmacSha1DeriveKey = 0x800020C2,
     /// Reserved for GlobalPlatform compliance test applications.
     IllegalValue = 0xefffffff,
 }
 
+impl AlgorithmId {
+    /// Return the algorithm with the raw value `value`, or `None` if it is unknown.
+    pub fn from_raw(value: u32) -> Option<Self> {
+        match value {
+            raw::TEE_ALG_AES_ECB_NOPAD => Some(AlgorithmId::AesEcbNopad),
+            raw::TEE_ALG_AES_CBC_NOPAD => Some(AlgorithmId::AesCbcNopad),
+            raw::TEE_ALG_AES_CTR => Some(AlgorithmId::AesCtr),
+            raw::TEE_ALG_AES_CTS => Some(AlgorithmId::AesCts),
+            raw::TEE_ALG_AES_XTS => Some(AlgorithmId::AesXts),
+            raw::TEE_ALG_AES_CBC_MAC_NOPAD => Some(AlgorithmId::AesCbcMacNopad),
+            raw::TEE_ALG_AES_CBC_MAC_PKCS5 => Some(AlgorithmId::AesCbcMacPkcs5),
+            raw::TEE_ALG_AES_CMAC => Some(AlgorithmId::AesCmac),
+            raw::TEE_ALG_AES_CCM => Some(AlgorithmId::AesCcm),
+            raw::TEE_ALG_AES_GCM => Some(AlgorithmId::AesGcm),
+            raw::TEE_ALG_DES_ECB_NOPAD => Some(AlgorithmId::DesEcbNopad),
+            raw::TEE_ALG_DES_CBC_NOPAD => Some(AlgorithmId::DesCbcNopad),
+            raw::TEE_ALG_DES_CBC_MAC_NOPAD => Some(AlgorithmId::DesCbcMacNopad),
+            raw::TEE_ALG_DES_CBC_MAC_PKCS5 => Some(AlgorithmId::DesCbcMacPkcs5),
+            raw::TEE_ALG_DES3_ECB_NOPAD => Some(AlgorithmId::Des3EcbNopad),
+            raw::TEE_ALG_DES3_CBC_NOPAD => Some(AlgorithmId::Des3CbcNopad),
+            raw::TEE_ALG_DES3_CBC_MAC_NOPAD => Some(AlgorithmId::Des3CbcMacNopad),
+            raw::TEE_ALG_DES3_CBC_MAC_PKCS5 => Some(AlgorithmId::Des3CbcMacPkcs5),
+            raw::TEE_ALG_RSASSA_PKCS1_V1_5_MD5 => Some(AlgorithmId::RsassaPkcs1V15MD5),
+            raw::TEE_ALG_RSASSA_PKCS1_V1_5_SHA1 => Some(AlgorithmId::RsassaPkcs1V15Sha1),
+            raw::TEE_ALG_RSASSA_PKCS1_V1_5_SHA224 => Some(AlgorithmId::RsassaPkcs1V15Sha224),
+            raw::TEE_ALG_RSASSA_PKCS1_V1_5_SHA256 => Some(AlgorithmId::RsassaPkcs1V15Sha256),
+            raw::TEE_ALG_RSASSA_PKCS1_V1_5_SHA384 => Some(AlgorithmId::RsassaPkcs1V15Sha384),
+            raw::TEE_ALG_RSASSA_PKCS1_V1_5_SHA512 => Some(AlgorithmId::RsassaPkcs1V15Sha512),
+            raw::TEE_ALG_RSASSA_PKCS1_V1_5_MD5SHA1 => Some(AlgorithmId::RsassaPkcs1V15MD5Sha1),
+            raw::TEE_ALG_RSASSA_PKCS1_PSS_MGF1_SHA1 => Some(AlgorithmId::RsassaPkcs1PssMgf1Sha1),
+            raw::TEE_ALG_RSASSA_PKCS1_PSS_MGF1_SHA224 => {
+                Some(AlgorithmId::RsassaPkcs1PssMgf1Sha224)
+            }
+            raw::TEE_ALG_RSASSA_PKCS1_PSS_MGF1_SHA256 => {
+                Some(AlgorithmId::RsassaPkcs1PssMgf1Sha256)
+            }
+            raw::TEE_ALG_RSASSA_PKCS1_PSS_MGF1_SHA384 => {
+                Some(AlgorithmId::RsassaPkcs1PssMgf1Sha384)
+            }
+            raw::TEE_ALG_RSASSA_PKCS1_PSS_MGF1_SHA512 => {
+                Some(AlgorithmId::RsassaPkcs1PssMgf1Sha512)
+            }
+            raw::TEE_ALG_RSAES_PKCS1_V1_5 => Some(AlgorithmId::RsaesPkcs1V15),
+            raw::TEE_ALG_RSAES_PKCS1_OAEP_MGF1_SHA1 => Some(AlgorithmId::RsaesPkcs1OAepMgf1Sha1),
+            raw::TEE_ALG_RSAES_PKCS1_OAEP_MGF1_SHA224 => {
+                Some(AlgorithmId::RsaesPkcs1OAepMgf1Sha224)
+            }
+            raw::TEE_ALG_RSAES_PKCS1_OAEP_MGF1_SHA256 => {
+                Some(AlgorithmId::RsaesPkcs1OAepMgf1Sha256)
+            }
+            raw::TEE_ALG_RSAES_PKCS1_OAEP_MGF1_SHA384 => {
+                Some(AlgorithmId::RsaesPkcs1OAepMgf1Sha384)
+            }
+            raw::TEE_ALG_RSAES_PKCS1_OAEP_MGF1_SHA512 => {
+                Some(AlgorithmId::RsaesPkcs1OAepMgf1Sha512)
+            }
+            raw::TEE_ALG_RSA_NOPAD => Some(AlgorithmId::RsaNopad),
+            raw::TEE_ALG_DSA_SHA1 => Some(AlgorithmId::DSASha1),
+            raw::TEE_ALG_DSA_SHA224 => Some(AlgorithmId::DSASha224),
+            raw::TEE_ALG_DSA_SHA256 => Some(AlgorithmId::DSASha256),
+            raw::TEE_ALG_DH_DERIVE_SHARED_SECRET => Some(AlgorithmId::DhDeriveSharedSecret),
+            raw::TEE_ALG_ECDSA_P192 => Some(AlgorithmId::EcdsaP192),
+            raw::TEE_ALG_ECDSA_P224 => Some(AlgorithmId::EcdsaP224),
+            raw::TEE_ALG_ECDSA_P256 => Some(AlgorithmId::EcdsaP256),
+            raw::TEE_ALG_ECDSA_P384 => Some(AlgorithmId::EcdsaP384),
+            raw::TEE_ALG_ECDSA_P521 => Some(AlgorithmId::EcdsaP521),
+            raw::TEE_ALG_ECDH_P192 => Some(AlgorithmId::EcdhP192),
+            raw::TEE_ALG_ECDH_P224 => Some(AlgorithmId::EcdhP224),
+            raw::TEE_ALG_ECDH_P256 => Some(AlgorithmId::EcdhP256),
+            raw::TEE_ALG_ECDH_P384 => Some(AlgorithmId::EcdhP384),
+            raw::TEE_ALG_ECDH_P521 => Some(AlgorithmId::EcdhP521),
+            raw::TEE_ALG_MD5 => Some(AlgorithmId::Md5),
+            raw::TEE_ALG_SHA1 => Some(AlgorithmId::Sha1),
+            raw::TEE_ALG_SHA224 => Some(AlgorithmId::Sha224),
+            raw::TEE_ALG_SHA256 => Some(AlgorithmId::Sha256),
+            raw::TEE_ALG_SHA384 => Some(AlgorithmId::Sha384),
+            raw::TEE_ALG_SHA512 => Some(AlgorithmId::Sha512),
+            raw::TEE_ALG_MD5SHA1 => Some(AlgorithmId::Md5Sha1),
+            raw::TEE_ALG_HMAC_MD5 => Some(AlgorithmId::HmacMd5),
+            raw::TEE_ALG_HMAC_SHA1 => Some(AlgorithmId::HmacSha1),
+            raw::TEE_ALG_HMAC_SHA224 => Some(AlgorithmId::HmacSha224),
+            raw::TEE_ALG_HMAC_SHA256 => Some(AlgorithmId::HmacSha256),
+            raw::TEE_ALG_HMAC_SHA384 => Some(AlgorithmId::HmacSha384),
+            raw::TEE_ALG_HMAC_SHA512 => Some(AlgorithmId::HmacSha512),
+            raw::TEE_ALG_HKDF_MD5_DERIVE_KEY => Some(AlgorithmId::HkdfMd5DeriveKey),
+            raw::TEE_ALG_HKDF_SHA1_DERIVE_KEY => Some(AlgorithmId::HkdfSha1DeriveKey),
+            raw::TEE_ALG_HKDF_SHA224_DERIVE_KEY => Some(AlgorithmId::HkdfSha224DeriveKey),
+            raw::TEE_ALG_HKDF_SHA256_DERIVE_KEY => Some(AlgorithmId::HkdfSha256DeriveKey),
+            raw::TEE_ALG_HKDF_SHA384_DERIVE_KEY => Some(AlgorithmId::HkdfSha384DeriveKey),
+            raw::TEE_ALG_HKDF_SHA512_DERIVE_KEY => Some(AlgorithmId::HkdfSha512DeriveKey),
+            raw::TEE_ALG_CONCAT_KDF_SHA1_DERIVE_KEY => Some(AlgorithmId::ConcatKdfSha1DeriveKey),
+            raw::TEE_ALG_CONCAT_KDF_SHA224_DERIVE_KEY => {
+                Some(AlgorithmId::ConcatKdfSha224DeriveKey)
+            }
+            raw::TEE_ALG_CONCAT_KDF_SHA256_DERIVE_KEY => {
+                Some(AlgorithmId::ConcatKdfSha256DeriveKey)
+            }
+            raw::TEE_ALG_CONCAT_KDF_SHA384_DERIVE_KEY => {
+                Some(AlgorithmId::ConcatKdfSha384DeriveKey)
+            }
+            raw::TEE_ALG_CONCAT_KDF_SHA512_DERIVE_KEY => {
+                Some(AlgorithmId::ConcatKdfSha512DeriveKey)
+            }
+            raw::TEE_ALG_PBKDF2_HMAC_SHA1_DERIVE_KEY => Some(AlgorithmId::Pbkdf2HmacSha1DeriveKey),
+            0xefffffff => Some(AlgorithmId::IllegalValue),
+            _ => None,
+        }
+    }
+}
+
 /// This specification defines support for optional cryptographic elements.
 #[repr(u32)]
 pub enum ElementId {
+    /// No element, for algorithms which are not defined over a curve.
+    ElementNone = 0x00000000,
     /// Source: `NIST`, Generic: `Y`, Size: 192 bits
     EccCurveNistP192 = 0x00000001,
     /// Source: `NIST`, Generic: `Y`, Size: 224 bits
@@ -1735,4 +2253,69 @@ pub enum ElementId {
     /// Source: `NIST`, Generic: `Y`, Size: 521 bits
     EccCurveNistP521 = 0x00000005,
 }
-//OP-TEE does not implement function: TEE_IsAlgorithmSuppddorted
+
+/// Check whether an algorithm, and for ECC algorithms a curve, is supported by the
+/// implementation, so that a TA can negotiate algorithms at runtime.
+///
+/// With the `is_algorithm_supported` feature, this calls `TEE_IsAlgorithmSupported`, which is
+/// missing in older versions of OP-TEE. Otherwise an operation is allocated with the algorithm
+/// and a typical key size, which does not check the curve.
+///
+/// # Parameters
+///
+/// 1) `algo`: The algorithm.
+/// 2) `element`: The curve for ECC algorithms, [ElementNone](ElementId::ElementNone) otherwise.
+///
+/// # Example
+///
+/// ```no_run
+/// let algo = if is_supported(AlgorithmId::EcdsaP256, ElementId::EccCurveNistP256) {
+///     AlgorithmId::EcdsaP256
+/// } else {
+///     AlgorithmId::RsassaPkcs1V15Sha256
+/// };
+/// ```
+pub fn is_supported(algo: AlgorithmId, element: ElementId) -> bool {
+    is_supported_raw(algo as u32, element as u32)
+}
+
+#[cfg(feature = "is_algorithm_supported")]
+fn is_supported_raw(algo: u32, element: u32) -> bool {
+    unsafe { raw::TEE_IsAlgorithmSupported(algo, element) == raw::TEE_SUCCESS }
+}
+
+#[cfg(not(feature = "is_algorithm_supported"))]
+fn is_supported_raw(algo: u32, element: u32) -> bool {
+    // The operation class is encoded in bits 28-31 and the main algorithm in bits 0-7.
+    let mode = match algo >> 28 {
+        0x3 => OperationMode::Mac,
+        0x5 => OperationMode::Digest,
+        0x7 => OperationMode::Sign,
+        0x8 => OperationMode::Derive,
+        _ => OperationMode::Encrypt,
+    } as u32;
+    let key_size = match algo & 0xff {
+        0x10 | 0x13 => 128,
+        0x11 => 64,
+        0x30 | 0x32 => 2048,
+        0x31 if (algo >> 12) & 0xf == 2 => 1024,
+        0x31 => 2048,
+        0x41 | 0x42 => match element {
+            raw::TEE_ECC_CURVE_NIST_P192 => 192,
+            raw::TEE_ECC_CURVE_NIST_P224 => 224,
+            raw::TEE_ECC_CURVE_NIST_P384 => 384,
+            raw::TEE_ECC_CURVE_NIST_P521 => 521,
+            _ => 256,
+        },
+        _ if mode == OperationMode::Digest as u32 => 0,
+        _ => 256,
+    };
+    let mut handle: raw::TEE_OperationHandle = ptr::null_mut();
+    match unsafe { raw::TEE_AllocateOperation(&mut handle, algo, mode, key_size) } {
+        raw::TEE_SUCCESS => {
+            unsafe { raw::TEE_FreeOperation(handle) };
+            true
+        }
+        _ => false,
+    }
+}
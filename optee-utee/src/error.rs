@@ -1,6 +1,9 @@
+use core::convert::From;
+use core::fmt;
+use optee_error::CustomError;
 use optee_utee_sys as raw;
-use std::convert::From;
-use std::fmt;
+#[cfg(feature = "std")]
+use std::io;
 
 /// A specialized [`Result`](https://doc.rust-lang.org/std/result/enum.Result.html)
 /// type for TEE operations.
@@ -12,7 +15,7 @@ use std::fmt;
 ///     Ok(())
 /// }
 /// ````
-pub type Result<T> = std::result::Result<T, Error>;
+pub type Result<T> = core::result::Result<T, Error>;
 
 pub struct Error {
     code: u32,
@@ -85,6 +88,8 @@ pub enum ErrorKind {
     TimeNeedsReset = 0xFFFF5001,
     /// Unknown error.
     Unknown,
+    /// An application-defined error, see [CustomError](trait.CustomError.html).
+    Custom = optee_error::CUSTOM_ERROR_BASE,
 }
 
 impl ErrorKind {
@@ -126,6 +131,7 @@ impl ErrorKind {
                 "The persistent time has been set but may have been corrupted and SHALL no longer be trusted."
             },
             ErrorKind::Unknown => "Unknown error.",
+            ErrorKind::Custom => "Application-defined error.",
         }
     }
 }
@@ -189,6 +195,7 @@ impl Error {
             raw::TEE_ERROR_SIGNATURE_INVALID => ErrorKind::SignatureInvalid,
             raw::TEE_ERROR_TIME_NOT_SET => ErrorKind::TimeNotSet,
             raw::TEE_ERROR_TIME_NEEDS_RESET => ErrorKind::TimeNeedsReset,
+            code if optee_error::is_custom_error(code) => ErrorKind::Custom,
             _ => ErrorKind::Unknown,
         }
     }
@@ -200,6 +207,38 @@ impl Error {
     pub fn message(&self) -> &str {
         self.kind().as_str()
     }
+
+    /// Returns the application-defined error, if the code belongs to `E`.
+    ///
+    /// # Examples
+    ///
+    /// ``` no_run
+    /// use optee_utee::{CustomError, Error};
+    ///
+    /// #[derive(Clone, Copy, CustomError, Debug, PartialEq)]
+    /// pub enum WalletError {
+    ///     PinBlocked,
+    /// }
+    ///
+    /// let error = Error::from(WalletError::PinBlocked);
+    /// assert_eq!(error.custom(), Some(WalletError::PinBlocked));
+    /// ```
+    pub fn custom<E: CustomError>(&self) -> Option<E> {
+        E::from_code(self.code)
+    }
+
+    /// Converts an `io::Error`, e.g. from the `stream` adapters. An `io::Error` created from an
+    /// `Error` gives back the original error.
+    ///
+    /// This is not a `From` implementation, which would overlap with the conversion from the
+    /// [CustomError](trait.CustomError.html) types.
+    #[cfg(feature = "std")]
+    pub fn from_io_error(error: io::Error) -> Error {
+        if let Some(error) = error.get_ref().and_then(|e| e.downcast_ref::<Error>()) {
+            return Error { code: error.code };
+        }
+        Error::from_raw_error(optee_error::io::error_code(error.kind()))
+    }
 }
 
 impl fmt::Debug for Error {
@@ -214,6 +253,7 @@ impl fmt::Display for Error {
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for Error {
     fn description(&self) -> &str {
         self.message()
@@ -226,3 +266,17 @@ impl From<ErrorKind> for Error {
         Error { code: kind as u32 }
     }
 }
+
+impl<E: CustomError> From<E> for Error {
+    #[inline]
+    fn from(error: E) -> Error {
+        Error { code: error.code() }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Error> for io::Error {
+    fn from(error: Error) -> io::Error {
+        io::Error::new(optee_error::io::error_kind(error.code), error)
+    }
+}
@@ -0,0 +1,259 @@
+use crate::{Error, ErrorKind, Result};
+use alloc::alloc::{alloc, GlobalAlloc, Layout};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use bitflags::bitflags;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::{mem, ptr};
+use optee_utee_sys as raw;
+
+bitflags! {
+    /// Hints given to `TEE_Malloc` for every allocation of a [TeeAllocator](TeeAllocator).
+    ///
+    /// The empty set is `TEE_MALLOC_FILL_ZERO`, which is the only hint supported by older
+    /// versions of OP-TEE. Other versions reject unknown hints, in which case every allocation
+    /// fails and is counted in [HeapStats::failed](HeapStats::failed).
+    pub struct MallocHint: u32 {
+        /// The allocated buffer is not filled with zeros.
+        const NO_FILL = 0x00000001;
+        /// The allocated buffer is never shared with another Trusted Application or the
+        /// normal world.
+        const NO_SHARE = 0x00000002;
+    }
+}
+
+/// The alignment of every buffer returned by `TEE_Malloc`.
+const MIN_ALIGN: usize = 8;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static FAILED: AtomicUsize = AtomicUsize::new(0);
+
+/// Statistics of the allocations made through a [TeeAllocator](TeeAllocator).
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct HeapStats {
+    /// The number of bytes currently allocated.
+    pub current: usize,
+    /// The highest number of bytes allocated at the same time.
+    pub peak: usize,
+    /// The number of allocations which failed.
+    pub failed: usize,
+}
+
+/// Return the statistics of the allocations made through a [TeeAllocator](TeeAllocator).
+///
+/// The numbers are the sizes requested by the Trusted Application and do not include the
+/// bookkeeping of the heap, so `TA_DATA_SIZE` needs some room above the peak.
+pub fn heap_stats() -> HeapStats {
+    HeapStats {
+        current: CURRENT.load(Ordering::Relaxed),
+        peak: PEAK.load(Ordering::Relaxed),
+        failed: FAILED.load(Ordering::Relaxed),
+    }
+}
+
+/// Reset the peak of the heap statistics to the number of bytes currently allocated, e.g. to
+/// measure a single command.
+pub fn reset_peak() {
+    PEAK.store(CURRENT.load(Ordering::Relaxed), Ordering::Relaxed);
+}
+
+fn record_alloc(size: usize) {
+    let current = CURRENT.load(Ordering::Relaxed) + size;
+    CURRENT.store(current, Ordering::Relaxed);
+    if current > PEAK.load(Ordering::Relaxed) {
+        PEAK.store(current, Ordering::Relaxed);
+    }
+}
+
+fn record_dealloc(size: usize) {
+    CURRENT.fetch_sub(size, Ordering::Relaxed);
+}
+
+fn record_failure() {
+    FAILED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// A global allocator using `TEE_Malloc`, `TEE_Realloc` and `TEE_Free`, which keeps the
+/// statistics returned by [heap_stats](heap_stats).
+///
+/// Without the `std` feature, the `global_allocator` feature declares a `TeeAllocator::new()` as
+/// the global allocator instead.
+///
+/// # Example
+///
+/// ``` no_run
+/// use optee_utee::heap::{self, TeeAllocator};
+///
+/// #[global_allocator]
+/// static ALLOCATOR: TeeAllocator = TeeAllocator::new();
+///
+/// #[ta_destroy]
+/// fn destroy() {
+///     trace_println!("heap peak: {} bytes", heap::heap_stats().peak);
+/// }
+/// ```
+pub struct TeeAllocator {
+    hint: u32,
+}
+
+impl TeeAllocator {
+    /// Create an allocator which fills the allocated buffers with zeros.
+    pub const fn new() -> Self {
+        Self {
+            hint: raw::TEE_MALLOC_FILL_ZERO,
+        }
+    }
+
+    /// Create an allocator which passes the `hint` to `TEE_Malloc`.
+    pub const fn with_hint(hint: MallocHint) -> Self {
+        Self { hint: hint.bits() }
+    }
+
+    fn fills_zero(&self) -> bool {
+        self.hint & raw::TEE_MALLOC_NO_FILL == 0
+    }
+
+    unsafe fn malloc(&self, size: usize) -> *mut u8 {
+        if size > u32::max_value() as usize {
+            return ptr::null_mut();
+        }
+        raw::TEE_Malloc(size as u32, self.hint) as *mut u8
+    }
+
+    // Buffers with a larger alignment than `TEE_Malloc` are over-allocated, and the pointer
+    // returned by `TEE_Malloc` is kept right before the aligned buffer.
+    unsafe fn alloc_raw(&self, layout: Layout) -> *mut u8 {
+        if layout.align() <= MIN_ALIGN {
+            return self.malloc(layout.size());
+        }
+        let size = match layout.size().checked_add(layout.align()) {
+            Some(size) => size,
+            None => return ptr::null_mut(),
+        };
+        let base = self.malloc(size);
+        if base.is_null() {
+            return base;
+        }
+        let offset = layout.align() - (base as usize + mem::size_of::<usize>()) % layout.align();
+        let aligned = base.add(mem::size_of::<usize>() + offset % layout.align());
+        *(aligned as *mut *mut u8).offset(-1) = base;
+        aligned
+    }
+
+    unsafe fn free_raw(&self, ptr: *mut u8, layout: Layout) {
+        let base = if layout.align() <= MIN_ALIGN {
+            ptr
+        } else {
+            *(ptr as *mut *mut u8).offset(-1)
+        };
+        raw::TEE_Free(base as _);
+    }
+}
+
+unsafe impl GlobalAlloc for TeeAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.alloc_raw(layout);
+        if ptr.is_null() {
+            record_failure();
+        } else {
+            record_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.alloc(layout);
+        if !ptr.is_null() && !self.fills_zero() {
+            ptr::write_bytes(ptr, 0, layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.free_raw(ptr, layout);
+        record_dealloc(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = if layout.align() <= MIN_ALIGN && new_size <= u32::max_value() as usize {
+            raw::TEE_Realloc(ptr as _, new_size as u32) as *mut u8
+        } else {
+            let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+            let new_ptr = self.alloc_raw(new_layout);
+            if !new_ptr.is_null() {
+                ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+                self.free_raw(ptr, layout);
+            }
+            new_ptr
+        };
+        if new_ptr.is_null() {
+            record_failure();
+        } else {
+            record_dealloc(layout.size());
+            record_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+#[cfg(all(not(feature = "std"), alloc_error_handler_required))]
+#[alloc_error_handler]
+fn alloc_error(layout: Layout) -> ! {
+    panic!("memory allocation of {} bytes failed", layout.size())
+}
+
+/// Allocate a vector with room for `capacity` elements.
+///
+/// Unlike `Vec::with_capacity`, which aborts the Trusted Application when the heap is exhausted,
+/// the failure is returned so that it can be reported to the client.
+///
+/// # Errors
+///
+/// 1) `OutOfMemory`: If the allocation fails.
+///
+/// # Example
+///
+/// ``` no_run
+/// let mut buffer = heap::try_vec_with_capacity::<u8>(len)?;
+/// buffer.extend_from_slice(input);
+/// ```
+pub fn try_vec_with_capacity<T>(capacity: usize) -> Result<Vec<T>> {
+    let size = match mem::size_of::<T>().checked_mul(capacity) {
+        Some(size) => size,
+        None => return Err(Error::new(ErrorKind::OutOfMemory)),
+    };
+    if size == 0 {
+        return Ok(Vec::with_capacity(capacity));
+    }
+    let layout = Layout::from_size_align(size, mem::align_of::<T>())
+        .map_err(|_| Error::new(ErrorKind::OutOfMemory))?;
+    let ptr = unsafe { alloc(layout) };
+    if ptr.is_null() {
+        return Err(Error::new(ErrorKind::OutOfMemory));
+    }
+    Ok(unsafe { Vec::from_raw_parts(ptr as *mut T, 0, capacity) })
+}
+
+/// Move `value` to the heap.
+///
+/// Unlike `Box::new`, the failure of the allocation is returned instead of aborting the Trusted
+/// Application.
+///
+/// # Errors
+///
+/// 1) `OutOfMemory`: If the allocation fails.
+pub fn try_box<T>(value: T) -> Result<Box<T>> {
+    let layout = Layout::new::<T>();
+    if layout.size() == 0 {
+        return Ok(Box::new(value));
+    }
+    let ptr = unsafe { alloc(layout) } as *mut T;
+    if ptr.is_null() {
+        return Err(Error::new(ErrorKind::OutOfMemory));
+    }
+    unsafe {
+        ptr::write(ptr, value);
+        Ok(Box::from_raw(ptr))
+    }
+}
@@ -0,0 +1,135 @@
+//! State shared by all the sessions of a Trusted Application instance, e.g. for TAs with the
+//! `TA_FLAG_SINGLE_INSTANCE` and `TA_FLAG_MULTI_SESSION` flags.
+
+use crate::{Error, ErrorKind, Result};
+use alloc::boxed::Box;
+use core::cell::{Cell, UnsafeCell};
+use core::marker;
+use optee_utee_sys as raw;
+
+struct Slot<T> {
+    borrowed: Cell<bool>,
+    value: UnsafeCell<T>,
+}
+
+/// The state of a Trusted Application instance, stored with `TEE_SetInstanceData`.
+///
+/// The state is created in the `#[ta_create]` entry point with [init](InstanceData::init), used
+/// by the sessions with [with](InstanceData::with) and dropped in the `#[ta_destroy]` entry point
+/// with [destroy](InstanceData::destroy). Since OP-TEE keeps a single pointer per instance, the
+/// state is declared with [ta_instance_state!](../macro.ta_instance_state.html), which makes sure
+/// that a Trusted Application does not declare two of them.
+///
+/// # Example
+///
+/// ```no_run
+/// ta_instance_state!(static CACHE: KeyCache);
+///
+/// #[ta_create]
+/// fn create() -> Result<()> {
+///     CACHE.init(KeyCache::new())
+/// }
+///
+/// #[ta_destroy]
+/// fn destroy() {
+///     CACHE.destroy();
+/// }
+///
+/// #[ta_invoke_command]
+/// fn invoke_command(cmd_id: u32, params: &mut Parameters) -> Result<()> {
+///     CACHE.with(|cache| cache.handle(cmd_id, params))?
+/// }
+/// ```
+pub struct InstanceData<T> {
+    _marker: marker::PhantomData<*mut T>,
+}
+
+// Trusted Applications are single threaded, the state is only accessed from the entry points of
+// the instance.
+unsafe impl<T: Send> Sync for InstanceData<T> {}
+
+impl<T> InstanceData<T> {
+    /// Create the handle on the state, which is initialized with [init](InstanceData::init).
+    ///
+    /// # Safety
+    ///
+    /// Every handle reads the single instance data pointer of OP-TEE as a `T`, so a Trusted
+    /// Application must not create two of them. Use
+    /// [ta_instance_state!](../macro.ta_instance_state.html) instead, which checks it.
+    #[doc(hidden)]
+    pub const unsafe fn new() -> Self {
+        Self {
+            _marker: marker::PhantomData,
+        }
+    }
+
+    fn slot(&self) -> Option<&Slot<T>> {
+        unsafe { (raw::TEE_GetInstanceData() as *const Slot<T>).as_ref() }
+    }
+
+    /// Store the state of the instance.
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadState`: If the state is already initialized.
+    pub fn init(&self, value: T) -> Result<()> {
+        if self.is_initialized() {
+            return Err(Error::new(ErrorKind::BadState));
+        }
+        let slot = Box::new(Slot {
+            borrowed: Cell::new(false),
+            value: UnsafeCell::new(value),
+        });
+        unsafe { raw::TEE_SetInstanceData(Box::into_raw(slot) as _) };
+        Ok(())
+    }
+
+    /// Return `true` if the state is initialized.
+    pub fn is_initialized(&self) -> bool {
+        self.slot().is_some()
+    }
+
+    /// Call `f` with a mutable reference to the state and return its result.
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadState`: If the state is not initialized.
+    /// 2) `AccessConflict`: If the state is already in use, i.e. if `with` is called from `f`.
+    pub fn with<R, F: FnOnce(&mut T) -> R>(&self, f: F) -> Result<R> {
+        let slot = match self.slot() {
+            Some(slot) => slot,
+            None => return Err(Error::new(ErrorKind::BadState)),
+        };
+        if slot.borrowed.replace(true) {
+            return Err(Error::new(ErrorKind::AccessConflict));
+        }
+        let _guard = BorrowGuard(&slot.borrowed);
+        Ok(f(unsafe { &mut *slot.value.get() }))
+    }
+
+    /// Drop the state, if it is initialized.
+    ///
+    /// # Panics
+    ///
+    /// 1) If the state is in use, i.e. if `destroy` is called from [with](InstanceData::with).
+    pub fn destroy(&self) {
+        if let Some(slot) = self.slot() {
+            if slot.borrowed.get() {
+                panic!("instance data destroyed while in use");
+            }
+            unsafe {
+                let slot = raw::TEE_GetInstanceData() as *mut Slot<T>;
+                raw::TEE_SetInstanceData(core::ptr::null());
+                drop(Box::from_raw(slot));
+            }
+        }
+    }
+}
+
+struct BorrowGuard<'a>(&'a Cell<bool>);
+
+impl<'a> Drop for BorrowGuard<'a> {
+    fn drop(&mut self) {
+        self.0.set(false);
+    }
+}
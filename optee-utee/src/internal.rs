@@ -1,6 +1,6 @@
-use crate::{Attribute, Error, ObjHandle, Result, TransientObject, DifferentParameters};
+use crate::{Attribute, Error, ObjHandle, Result, TransientObject, DifferentParameters, Uuid};
 use optee_utee_sys as raw;
-use std::{mem, ptr};
+use core::{mem, ptr};
 
 /// An abstraction of the logical connection between a Trusted Application (TA)
 /// and another TA
@@ -12,14 +12,15 @@ pub struct Session {
 
 impl Session {
     pub fn new(uuid_time_low: u32, uuid_time_mid: u16, uuid_time_hi_and_version: u16, uuid_clock_seq_and_node: [u8;8] ) -> Result<Self> {
+        Self::open(Uuid::from_fields(uuid_time_low, uuid_time_mid, uuid_time_hi_and_version, uuid_clock_seq_and_node))
+    }
+
+    /// Open a session with the Trusted Application `uuid`, e.g. the `UUID` constant exported by
+    /// its proto crate.
+    pub fn open(uuid: Uuid) -> Result<Self> {
         let mut handle:raw::TEE_TASessionHandle = ptr::null_mut();
         let mut return_origin: u32 = 0;
-        let uuid = raw::TEE_UUID {
-            timeLow: uuid_time_low,
-            timeMid: uuid_time_mid,
-            timeHiAndVersion: uuid_time_hi_and_version,
-            clockSeqAndNode: uuid_clock_seq_and_node,
-        };
+        let uuid = uuid.to_tee_uuid();
         unsafe {
             match raw::TEE_OpenTASession(&uuid, // destination
                                         0, // cancellationRequestTimeout
@@ -0,0 +1,485 @@
+//! Key derivation functions.
+//!
+//! This module provides HKDF ([RFC 5869](https://tools.ietf.org/html/rfc5869)), PBKDF2-HMAC
+//! ([RFC 8018](https://tools.ietf.org/html/rfc8018)) and the single-step Concat KDF of NIST
+//! SP 800-56A. When OP-TEE is built with the corresponding `CFG_CRYPTO_*` option, the derivation
+//! is done by the `TEE_ALG_HKDF_*`, `TEE_ALG_PBKDF2_*` or `TEE_ALG_CONCAT_KDF_*` algorithm of the
+//! core; otherwise it falls back to an implementation on top of [Mac](../crypto_op/struct.Mac.html)
+//! and [Digest](../crypto_op/struct.Digest.html). Either way the derived key is returned as a
+//! [GenericSecret](../object/enum.TransientObjectType.html#variant.GenericSecret) transient object.
+
+use crate::{
+    AlgorithmId, Attribute, AttributeId, AttributeMemref, AttributeValue, DeriveKey, Digest, Error,
+    ErrorKind, Mac, Result, SecretVec, TransientObject, TransientObjectType,
+};
+use alloc::vec;
+use core::{cmp, mem};
+
+/// Maximum size in bytes of a [GenericSecret](TransientObjectType::GenericSecret) object.
+const MAX_SECRET_LEN: usize = 512;
+
+/// Hash functions that can be used by the key derivation functions.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KdfHash {
+    /// SHA-1, 20 bytes output.
+    Sha1,
+    /// SHA-224, 28 bytes output.
+    Sha224,
+    /// SHA-256, 32 bytes output.
+    Sha256,
+    /// SHA-384, 48 bytes output.
+    Sha384,
+    /// SHA-512, 64 bytes output.
+    Sha512,
+}
+
+impl KdfHash {
+    /// Return the output length of the hash function in bytes.
+    pub fn output_len(&self) -> usize {
+        match self {
+            KdfHash::Sha1 => 20,
+            KdfHash::Sha224 => 28,
+            KdfHash::Sha256 => 32,
+            KdfHash::Sha384 => 48,
+            KdfHash::Sha512 => 64,
+        }
+    }
+
+    pub(crate) fn from_hmac_algorithm(algo: AlgorithmId) -> Option<KdfHash> {
+        match algo {
+            AlgorithmId::HmacSha1 => Some(KdfHash::Sha1),
+            AlgorithmId::HmacSha224 => Some(KdfHash::Sha224),
+            AlgorithmId::HmacSha256 => Some(KdfHash::Sha256),
+            AlgorithmId::HmacSha384 => Some(KdfHash::Sha384),
+            AlgorithmId::HmacSha512 => Some(KdfHash::Sha512),
+            _ => None,
+        }
+    }
+
+    fn block_len(&self) -> usize {
+        match self {
+            KdfHash::Sha384 | KdfHash::Sha512 => 128,
+            _ => 64,
+        }
+    }
+
+    fn digest_algorithm(&self) -> AlgorithmId {
+        match self {
+            KdfHash::Sha1 => AlgorithmId::Sha1,
+            KdfHash::Sha224 => AlgorithmId::Sha224,
+            KdfHash::Sha256 => AlgorithmId::Sha256,
+            KdfHash::Sha384 => AlgorithmId::Sha384,
+            KdfHash::Sha512 => AlgorithmId::Sha512,
+        }
+    }
+
+    fn hmac_algorithm(&self) -> AlgorithmId {
+        match self {
+            KdfHash::Sha1 => AlgorithmId::HmacSha1,
+            KdfHash::Sha224 => AlgorithmId::HmacSha224,
+            KdfHash::Sha256 => AlgorithmId::HmacSha256,
+            KdfHash::Sha384 => AlgorithmId::HmacSha384,
+            KdfHash::Sha512 => AlgorithmId::HmacSha512,
+        }
+    }
+
+    fn hmac_key_type(&self) -> TransientObjectType {
+        match self {
+            KdfHash::Sha1 => TransientObjectType::HmacSha1,
+            KdfHash::Sha224 => TransientObjectType::HmacSha224,
+            KdfHash::Sha256 => TransientObjectType::HmacSha256,
+            KdfHash::Sha384 => TransientObjectType::HmacSha384,
+            KdfHash::Sha512 => TransientObjectType::HmacSha512,
+        }
+    }
+
+    fn hkdf_algorithm(&self) -> AlgorithmId {
+        match self {
+            KdfHash::Sha1 => AlgorithmId::HkdfSha1DeriveKey,
+            KdfHash::Sha224 => AlgorithmId::HkdfSha224DeriveKey,
+            KdfHash::Sha256 => AlgorithmId::HkdfSha256DeriveKey,
+            KdfHash::Sha384 => AlgorithmId::HkdfSha384DeriveKey,
+            KdfHash::Sha512 => AlgorithmId::HkdfSha512DeriveKey,
+        }
+    }
+
+    fn concat_kdf_algorithm(&self) -> AlgorithmId {
+        match self {
+            KdfHash::Sha1 => AlgorithmId::ConcatKdfSha1DeriveKey,
+            KdfHash::Sha224 => AlgorithmId::ConcatKdfSha224DeriveKey,
+            KdfHash::Sha256 => AlgorithmId::ConcatKdfSha256DeriveKey,
+            KdfHash::Sha384 => AlgorithmId::ConcatKdfSha384DeriveKey,
+            KdfHash::Sha512 => AlgorithmId::ConcatKdfSha512DeriveKey,
+        }
+    }
+
+    // OP-TEE only implements PBKDF2 with HMAC-SHA1.
+    fn pbkdf2_algorithm(&self) -> Option<AlgorithmId> {
+        match self {
+            KdfHash::Sha1 => Some(AlgorithmId::Pbkdf2HmacSha1DeriveKey),
+            _ => None,
+        }
+    }
+}
+
+/// HMAC-based extract-and-expand key derivation function (HKDF).
+pub struct Hkdf();
+
+impl Hkdf {
+    /// Derive a key of `okm_len` bytes from the input keying material, i.e. run HKDF-Extract
+    /// followed by HKDF-Expand.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `hash`: Hash function used by HMAC.
+    /// 2) `ikm`: Input keying material, e.g. a Diffie-Hellman shared secret.
+    /// 3) `salt`: Optional salt, an empty slice is equivalent to a string of zeros.
+    /// 4) `info`: Context and application specific information, can be empty.
+    /// 5) `okm_len`: Length of the output keying material in bytes.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let shared_secret = [0u8; 32];
+    /// let session_key = Hkdf::derive(KdfHash::Sha256, &shared_secret, b"salt", b"session", 16)?;
+    /// let mut key = [0u8; 16];
+    /// session_key.ref_attribute(AttributeId::SecretValue, &mut key)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadParameters`: If `okm_len` is zero, larger than `255 * hash.output_len()` or larger
+    ///    than the maximum size of a [GenericSecret](TransientObjectType::GenericSecret) object.
+    /// 2) `OutOfMemory`: If not enough resources are available to allocate the objects.
+    ///
+    /// # Panics
+    ///
+    /// 1) Hardware or cryptographic algorithm failure.
+    /// 2) If the Implementation detects any other error.
+    pub fn derive(
+        hash: KdfHash,
+        ikm: &[u8],
+        salt: &[u8],
+        info: &[u8],
+        okm_len: usize,
+    ) -> Result<TransientObject> {
+        check_output_len(okm_len, 255 * hash.output_len())?;
+        let params = [
+            AttributeMemref::from_ref(AttributeId::HkdfSalt, salt).into(),
+            AttributeMemref::from_ref(AttributeId::HkdfInfo, info).into(),
+            AttributeValue::from_value(AttributeId::HkdfOkmLength, okm_len as u32, 0).into(),
+        ];
+        // An empty salt attribute is rejected by some versions of OP-TEE, omit it instead.
+        let params = if salt.is_empty() {
+            &params[1..]
+        } else {
+            &params[..]
+        };
+        let native = derive_native(
+            hash.hkdf_algorithm(),
+            TransientObjectType::HkdfIkm,
+            AttributeId::HkdfIkm,
+            ikm,
+            params,
+            okm_len,
+        )?;
+        if let Some(object) = native {
+            return Ok(object);
+        }
+
+        let mut prk = SecretVec::zeroed(hash.output_len());
+        Self::extract(hash, salt, ikm, prk.expose_mut())?;
+        Self::expand(hash, prk.expose(), info, okm_len)
+    }
+
+    /// HKDF-Extract: compute the pseudorandom key `PRK = HMAC-Hash(salt, IKM)`.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `hash`: Hash function used by HMAC.
+    /// 2) `salt`: Optional salt, an empty slice is equivalent to a string of zeros.
+    /// 3) `ikm`: Input keying material.
+    /// 4) `prk`: Output buffer of at least `hash.output_len()` bytes.
+    ///
+    /// # Errors
+    ///
+    /// 1) `ShortBuffer`: If `prk` is shorter than `hash.output_len()`.
+    /// 2) `OutOfMemory`: If not enough resources are available to allocate the operation.
+    pub fn extract(hash: KdfHash, salt: &[u8], ikm: &[u8], prk: &mut [u8]) -> Result<usize> {
+        if prk.len() < hash.output_len() {
+            return Err(Error::new(ErrorKind::ShortBuffer));
+        }
+        let zeros = vec![0u8; hash.output_len()];
+        let salt = if salt.is_empty() { &zeros[..] } else { salt };
+        Hmac::new(hash, salt)?.compute(&[ikm], prk)
+    }
+
+    /// HKDF-Expand: expand a pseudorandom key, e.g. the output of [extract](Hkdf::extract), to
+    /// `okm_len` bytes of output keying material.
+    ///
+    /// Errors are the same as [derive](Hkdf::derive). This step is always computed with
+    /// [Mac](../crypto_op/struct.Mac.html) since OP-TEE only provides the combined derivation.
+    pub fn expand(
+        hash: KdfHash,
+        prk: &[u8],
+        info: &[u8],
+        okm_len: usize,
+    ) -> Result<TransientObject> {
+        check_output_len(okm_len, 255 * hash.output_len())?;
+        let hmac = Hmac::new(hash, prk)?;
+        let hash_len = hash.output_len();
+        let mut okm = SecretVec::zeroed(okm_len);
+        let mut t = SecretVec::zeroed(hash_len);
+        let mut offset = 0;
+        while offset < okm_len {
+            // T(i) = HMAC-Hash(PRK, T(i - 1) | info | i), where T(0) is empty
+            let prev = if offset == 0 {
+                &[][..]
+            } else {
+                &okm.expose()[offset - hash_len..offset]
+            };
+            let counter = [(offset / hash_len + 1) as u8];
+            hmac.compute(&[prev, info, &counter], t.expose_mut())?;
+            let len = cmp::min(hash_len, okm_len - offset);
+            okm.expose_mut()[offset..offset + len].copy_from_slice(&t.expose()[..len]);
+            offset += len;
+        }
+        secret_object(okm.expose())
+    }
+}
+
+/// Password-based key derivation function 2 (PBKDF2) with HMAC as pseudorandom function.
+pub struct Pbkdf2();
+
+impl Pbkdf2 {
+    /// Derive a key of `dk_len` bytes from a password.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `hash`: Hash function used by HMAC. Only [Sha1](KdfHash::Sha1) is implemented by
+    ///    OP-TEE, the other ones always use the fallback implementation.
+    /// 2) `password`: The password.
+    /// 3) `salt`: The salt.
+    /// 4) `iterations`: Iteration count, at least 1.
+    /// 5) `dk_len`: Length of the derived key in bytes.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let key = Pbkdf2::derive(KdfHash::Sha256, b"password", b"salt", 10000, 32)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadParameters`: If `iterations` is zero, or if `dk_len` is zero or larger than the
+    ///    maximum size of a [GenericSecret](TransientObjectType::GenericSecret) object.
+    /// 2) `OutOfMemory`: If not enough resources are available to allocate the objects.
+    ///
+    /// # Panics
+    ///
+    /// 1) Hardware or cryptographic algorithm failure.
+    /// 2) If the Implementation detects any other error.
+    pub fn derive(
+        hash: KdfHash,
+        password: &[u8],
+        salt: &[u8],
+        iterations: u32,
+        dk_len: usize,
+    ) -> Result<TransientObject> {
+        if iterations == 0 {
+            return Err(Error::new(ErrorKind::BadParameters));
+        }
+        check_output_len(dk_len, MAX_SECRET_LEN)?;
+        if let Some(algo) = hash.pbkdf2_algorithm() {
+            let params = [
+                AttributeMemref::from_ref(AttributeId::Pbkdf2Salt, salt).into(),
+                AttributeValue::from_value(AttributeId::Pbkdf2IterationCount, iterations, 0).into(),
+                AttributeValue::from_value(AttributeId::Pbkdf2DkmLength, dk_len as u32, 0).into(),
+            ];
+            let native = derive_native(
+                algo,
+                TransientObjectType::Pbkdf2Password,
+                AttributeId::Pbkdf2Password,
+                password,
+                &params,
+                dk_len,
+            )?;
+            if let Some(object) = native {
+                return Ok(object);
+            }
+        }
+
+        let hmac = Hmac::new(hash, password)?;
+        let mut dk = SecretVec::zeroed(dk_len);
+        let mut u = SecretVec::zeroed(hash.output_len());
+        // The previous block of the chain, swapped with `u` so that no iteration allocates.
+        let mut prev = SecretVec::zeroed(hash.output_len());
+        let mut t = SecretVec::zeroed(hash.output_len());
+        for (i, chunk) in dk.expose_mut().chunks_mut(hash.output_len()).enumerate() {
+            let index = ((i + 1) as u32).to_be_bytes();
+            hmac.compute(&[salt, &index], u.expose_mut())?;
+            t.expose_mut().copy_from_slice(u.expose());
+            for _ in 1..iterations {
+                mem::swap(&mut u, &mut prev);
+                hmac.compute(&[prev.expose()], u.expose_mut())?;
+                for (t, u) in t.expose_mut().iter_mut().zip(u.expose().iter()) {
+                    *t ^= *u;
+                }
+            }
+            chunk.copy_from_slice(&t.expose()[..chunk.len()]);
+        }
+        secret_object(dk.expose())
+    }
+}
+
+/// Single-step key derivation function of NIST SP 800-56A, based on a hash function.
+pub struct ConcatKdf();
+
+impl ConcatKdf {
+    /// Derive a key of `dkm_len` bytes from a shared secret, i.e. the concatenation of
+    /// `Hash(counter || Z || OtherInfo)` for `counter` starting at 1.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `hash`: Hash function.
+    /// 2) `z`: Shared secret.
+    /// 3) `other_info`: Context information, e.g. the encoded algorithm identifier and party
+    ///    information.
+    /// 4) `dkm_len`: Length of the derived keying material in bytes.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let shared_secret = [0u8; 32];
+    /// let key = ConcatKdf::derive(KdfHash::Sha256, &shared_secret, b"other info", 32)?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadParameters`: If `dkm_len` is zero or larger than the maximum size of a
+    ///    [GenericSecret](TransientObjectType::GenericSecret) object.
+    /// 2) `OutOfMemory`: If not enough resources are available to allocate the objects.
+    ///
+    /// # Panics
+    ///
+    /// 1) Hardware or cryptographic algorithm failure.
+    /// 2) If the Implementation detects any other error.
+    pub fn derive(
+        hash: KdfHash,
+        z: &[u8],
+        other_info: &[u8],
+        dkm_len: usize,
+    ) -> Result<TransientObject> {
+        check_output_len(dkm_len, MAX_SECRET_LEN)?;
+        let params = [
+            AttributeMemref::from_ref(AttributeId::ConcatKdfOtherInfo, other_info).into(),
+            AttributeValue::from_value(AttributeId::ConcatKdfDkmLength, dkm_len as u32, 0).into(),
+        ];
+        let native = derive_native(
+            hash.concat_kdf_algorithm(),
+            TransientObjectType::ConcatKdfZ,
+            AttributeId::ConcatKdfZ,
+            z,
+            &params,
+            dkm_len,
+        )?;
+        if let Some(object) = native {
+            return Ok(object);
+        }
+
+        let digest = Digest::allocate(hash.digest_algorithm())?;
+        let mut dkm = SecretVec::zeroed(dkm_len);
+        let mut k = SecretVec::zeroed(hash.output_len());
+        for (i, chunk) in dkm.expose_mut().chunks_mut(hash.output_len()).enumerate() {
+            digest.update(&((i + 1) as u32).to_be_bytes());
+            digest.update(z);
+            digest.do_final(other_info, k.expose_mut())?;
+            chunk.copy_from_slice(&k.expose()[..chunk.len()]);
+        }
+        secret_object(dkm.expose())
+    }
+}
+
+/// An HMAC operation keyed with an arbitrary length key.
+pub(crate) struct Hmac {
+    op: Mac,
+}
+
+impl Hmac {
+    pub(crate) fn new(hash: KdfHash, key: &[u8]) -> Result<Self> {
+        // HMAC hashes keys longer than the block size and pads shorter ones with zeros. Doing
+        // this here yields the same MAC and keeps the key within the sizes accepted by the
+        // HMAC object types, e.g. at least 192 bits for HMAC-SHA256.
+        let mut block = SecretVec::zeroed(hash.block_len());
+        if key.len() > block.expose().len() {
+            let digest = Digest::allocate(hash.digest_algorithm())?;
+            digest.do_final(key, block.expose_mut())?;
+        } else {
+            block.expose_mut()[..key.len()].copy_from_slice(key);
+        }
+        let block = block.expose();
+        let mut key = TransientObject::allocate(hash.hmac_key_type(), block.len() * 8)?;
+        key.populate(&[AttributeMemref::from_ref(AttributeId::SecretValue, block).into()])?;
+        let op = Mac::allocate(hash.hmac_algorithm(), block.len() * 8)?;
+        op.set_key(&key)?;
+        Ok(Self { op })
+    }
+
+    pub(crate) fn compute(&self, chunks: &[&[u8]], out: &mut [u8]) -> Result<usize> {
+        self.op.init(&[]);
+        for chunk in chunks {
+            self.op.update(chunk);
+        }
+        self.op.compute_final(&[], out)
+    }
+
+    pub(crate) fn verify(&self, chunks: &[&[u8]], mac: &[u8]) -> Result<()> {
+        self.op.init(&[]);
+        for chunk in chunks {
+            self.op.update(chunk);
+        }
+        self.op.compare_final(&[], mac)
+    }
+}
+
+/// Try to derive the key with the algorithm implemented in OP-TEE core. Return `None` if the
+/// algorithm is not supported so that the caller can fall back to its own implementation.
+fn derive_native(
+    algo: AlgorithmId,
+    key_type: TransientObjectType,
+    key_attr: AttributeId,
+    key: &[u8],
+    params: &[Attribute],
+    out_len: usize,
+) -> Result<Option<TransientObject>> {
+    let key_size = key.len() * 8;
+    let op = match DeriveKey::allocate(algo, key_size) {
+        Ok(op) => op,
+        Err(ref e) if e.kind() == ErrorKind::NotSupported => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut key_object = match TransientObject::allocate(key_type, key_size) {
+        Ok(object) => object,
+        Err(ref e) if e.kind() == ErrorKind::NotSupported => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    key_object.populate(&[AttributeMemref::from_ref(key_attr, key).into()])?;
+    op.set_key(&key_object)?;
+    let mut object = TransientObject::allocate(TransientObjectType::GenericSecret, out_len * 8)?;
+    op.derive(params, &mut object);
+    Ok(Some(object))
+}
+
+fn secret_object(secret: &[u8]) -> Result<TransientObject> {
+    let mut object =
+        TransientObject::allocate(TransientObjectType::GenericSecret, secret.len() * 8)?;
+    object.populate(&[AttributeMemref::from_ref(AttributeId::SecretValue, secret).into()])?;
+    Ok(object)
+}
+
+fn check_output_len(len: usize, max: usize) -> Result<()> {
+    if len == 0 || len > max || len > MAX_SECRET_LEN {
+        return Err(Error::new(ErrorKind::BadParameters));
+    }
+    Ok(())
+}
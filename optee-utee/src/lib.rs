@@ -1,10 +1,34 @@
+//! The TEE internal core API for Trusted Applications.
+//!
+//! The crate only needs `core` and `alloc`. The default `std` feature builds it on the std port of
+//! the `rust/` directory and adds the `std::io` implementations. Without it, Trusted Applications
+//! are built with a stock `no_std` toolchain, and the crate provides the panic handler, which
+//! prints the panic message to the trace output before calling `TEE_Panic`. The global allocator
+//! is left to the Trusted Application, or is a [TeeAllocator](heap/struct.TeeAllocator.html) with
+//! the `global_allocator` feature.
+
+#![no_std]
+#![cfg_attr(
+    all(not(feature = "std"), alloc_error_handler_required),
+    feature(alloc_error_handler)
+)]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
 pub use self::error::{Error, ErrorKind, Result};
 pub use self::object::*;
 pub use self::crypto_op::*;
 pub use self::time::*;
 pub use self::arithmetical::*;
 pub use self::internal::*;
+pub use self::secret::*;
+pub use self::ta::{TaSession, TrustedApplication};
+pub use self::instance::InstanceData;
 pub use self::parameter::{ParamType, ParamTypes, Parameter, DifferentParameter, DifferentParameters, Parameters};
+pub use optee_error::CustomError;
+pub use optee_uuid::{uuid, Uuid};
 pub use optee_utee_macros::{
     ta_close_session, ta_create, ta_destroy, ta_invoke_command, ta_open_session,
 };
@@ -19,3 +43,19 @@ pub mod crypto_op;
 pub mod time;
 pub mod arithmetical;
 pub mod internal;
+pub mod kdf;
+pub mod crypto;
+#[cfg(feature = "std")]
+pub mod stream;
+pub mod heap;
+pub mod secret;
+pub mod mem;
+pub mod random;
+pub mod ta;
+pub mod instance;
+#[cfg(not(feature = "std"))]
+mod panic;
+
+#[cfg(all(not(feature = "std"), feature = "global_allocator"))]
+#[global_allocator]
+static ALLOCATOR: heap::TeeAllocator = heap::TeeAllocator::new();
@@ -42,3 +42,155 @@ macro_rules! trace_println {
         $crate::trace::Trace::_print(format_args!(concat!($s, "\n"), $($tt)*));
     };
 }
+
+/// Macro for printing an error message to the trace output, with the `ERR` prefix, the source
+/// location and a newline.
+///
+/// The message is printed if `TraceLevel::Error` is lower than or equal to both the compile-time
+/// [MAX_LEVEL](trace/constant.MAX_LEVEL.html), set with the `trace_level_*` features, and the
+/// runtime trace level. The comparison with `MAX_LEVEL` is done on constants, so the message is
+/// compiled out below the threshold. The macros work in any crate, e.g. a library used by the
+/// Trusted Application.
+///
+/// # Examples
+///
+/// ``` no_run
+/// trace_error!("failed to open object: {}", e);
+/// ```
+#[macro_export]
+macro_rules! trace_error {
+    ($($arg:tt)+) => ($crate::__trace_level!($crate::trace::TraceLevel::Error, $($arg)+));
+}
+
+/// Macro for printing an informational message to the trace output, with the `INF` prefix. See
+/// [trace_error!](macro.trace_error.html).
+#[macro_export]
+macro_rules! trace_info {
+    ($($arg:tt)+) => ($crate::__trace_level!($crate::trace::TraceLevel::Info, $($arg)+));
+}
+
+/// Macro for printing a debug message to the trace output, with the `DBG` prefix. See
+/// [trace_error!](macro.trace_error.html).
+#[macro_export]
+macro_rules! trace_debug {
+    ($($arg:tt)+) => ($crate::__trace_level!($crate::trace::TraceLevel::Debug, $($arg)+));
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __trace_level {
+    ($level:expr, $($arg:tt)+) => {
+        if $level as i32 <= $crate::trace::MAX_LEVEL {
+            $crate::trace::Trace::_print_level($level, file!(), line!(), format_args!($($arg)+));
+        }
+    };
+}
+
+/// Macro for making the `getrandom` crate use the random number generator of the TEE, so that
+/// crates depending on it work inside the Trusted Application. It must be invoked once, in the
+/// crate of the Trusted Application, since `getrandom` looks for the generator when the TA is
+/// linked.
+///
+/// # Examples
+///
+/// ``` no_run
+/// #![no_main]
+///
+/// optee_utee::register_getrandom!();
+/// ```
+#[cfg(feature = "getrandom")]
+#[macro_export]
+macro_rules! register_getrandom {
+    () => {
+        #[no_mangle]
+        unsafe extern "C" fn __getrandom_custom(dest: *mut u8, len: usize) -> u32 {
+            $crate::random::_getrandom_custom(dest, len)
+        }
+    };
+}
+
+/// Macro for generating the entry points of a Trusted Application from a type implementing
+/// [TrustedApplication](ta/trait.TrustedApplication.html). It replaces the `#[ta_create]`,
+/// `#[ta_destroy]`, `#[ta_open_session]`, `#[ta_close_session]` and `#[ta_invoke_command]`
+/// attributes.
+///
+/// The session returned by [TaSession::open](ta/trait.TaSession.html#tymethod.open) is kept until
+/// the client closes the session, then [TaSession::close](ta/trait.TaSession.html#method.close)
+/// consumes it, so the session is dropped exactly once whatever the results of the commands.
+///
+/// # Examples
+///
+/// ``` no_run
+/// struct MyTa;
+///
+/// impl TrustedApplication for MyTa {
+///     type Session = MySession;
+/// }
+///
+/// ta_main!(MyTa);
+/// ```
+#[macro_export]
+macro_rules! ta_main {
+    ($ta:ty) => {
+        #[no_mangle]
+        pub extern "C" fn TA_CreateEntryPoint() -> $crate::ta::_Result {
+            $crate::ta::_create::<$ta>()
+        }
+
+        #[no_mangle]
+        pub extern "C" fn TA_DestroyEntryPoint() {
+            $crate::ta::_destroy::<$ta>()
+        }
+
+        #[no_mangle]
+        pub extern "C" fn TA_OpenSessionEntryPoint(
+            param_types: u32,
+            params: &mut [$crate::ta::_Param; 4],
+            sess_ctx: *mut *mut ::core::ffi::c_void,
+        ) -> $crate::ta::_Result {
+            unsafe { $crate::ta::_open_session::<$ta>(param_types, params, sess_ctx) }
+        }
+
+        #[no_mangle]
+        pub extern "C" fn TA_CloseSessionEntryPoint(sess_ctx: *mut ::core::ffi::c_void) {
+            unsafe { $crate::ta::_close_session::<$ta>(sess_ctx) }
+        }
+
+        #[no_mangle]
+        pub extern "C" fn TA_InvokeCommandEntryPoint(
+            sess_ctx: *mut ::core::ffi::c_void,
+            cmd_id: u32,
+            param_types: u32,
+            params: &mut [$crate::ta::_Param; 4],
+        ) -> $crate::ta::_Result {
+            unsafe {
+                $crate::ta::_invoke_command::<$ta>(sess_ctx, cmd_id, param_types, params)
+            }
+        }
+    };
+}
+
+/// Macro for declaring the [InstanceData](instance/struct.InstanceData.html) holding the state
+/// shared by the sessions of the Trusted Application instance.
+///
+/// OP-TEE keeps a single instance data pointer, so declaring two states in the same Trusted
+/// Application fails to build.
+///
+/// # Examples
+///
+/// ``` no_run
+/// ta_instance_state!(static CACHE: KeyCache);
+/// ```
+#[macro_export]
+macro_rules! ta_instance_state {
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty $(;)?) => {
+        // Safe since the symbol below makes a second declaration fail to build.
+        $(#[$attr])*
+        $vis static $name: $crate::InstanceData<$t> = unsafe { $crate::InstanceData::new() };
+
+        #[no_mangle]
+        #[doc(hidden)]
+        #[allow(non_upper_case_globals)]
+        pub static __optee_utee_instance_state: u8 = 0;
+    };
+}
@@ -0,0 +1,136 @@
+//! Memory helpers for handling secrets and buffers shared with the client.
+
+use crate::{Error, Result};
+use bitflags::bitflags;
+use core::ops::Range;
+use core::ptr;
+use optee_utee_sys as raw;
+
+bitflags! {
+    /// The access rights checked by [check_access_rights](check_access_rights).
+    pub struct AccessFlags: u32 {
+        /// The buffer is readable by the Trusted Application.
+        const READ = raw::TEE_MEMORY_ACCESS_READ;
+        /// The buffer is writable by the Trusted Application.
+        const WRITE = raw::TEE_MEMORY_ACCESS_WRITE;
+        /// The buffer may be shared with the client or other Trusted Applications. Without this
+        /// flag, the buffer must be owned exclusively by the Trusted Application instance.
+        const ANY_OWNER = raw::TEE_MEMORY_ACCESS_ANY_OWNER;
+        /// The buffer lives in non-secure memory shared with the normal world. This is an
+        /// extension of OP-TEE.
+        const SHARED = raw::TEE_MEMORY_ACCESS_NONSECURE;
+        /// The buffer lives in secure memory. This is an extension of OP-TEE.
+        const SECURE = raw::TEE_MEMORY_ACCESS_SECURE;
+    }
+}
+
+/// Compare two buffers in a time which does not depend on their content, e.g. to check a MAC or
+/// an authentication tag. The lengths are not considered secret.
+///
+/// # Parameters
+///
+/// 1) `a`, `b`: The buffers to compare.
+///
+/// # Example
+///
+/// ```no_run
+/// mac.compute_final(message, &mut expected)?;
+/// if !mem::ct_eq(&expected, tag) {
+///     return Err(Error::new(ErrorKind::MacInvalid));
+/// }
+/// ```
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut diff = 0u8;
+    for (x, y) in a.iter().zip(b.iter()) {
+        // The volatile read hides the accumulated value from the compiler at every step, so that
+        // the loop cannot be turned into an early exit comparison.
+        diff = unsafe { ptr::read_volatile(&diff) } | (x ^ y);
+    }
+    diff == 0
+}
+
+/// Copy the bytes of `src` to the position `dest` of the same buffer using `TEE_MemMove`, the
+/// two regions may overlap. This is the counterpart of `slice::copy_within`.
+///
+/// # Parameters
+///
+/// 1) `buffer`: The buffer holding both regions.
+/// 2) `src`: The range of the bytes to copy.
+/// 3) `dest`: The start of the destination region, which has the length of `src`.
+///
+/// # Example
+///
+/// ```no_run
+/// let mut buffer = *b"header:payload";
+/// mem::move_within(&mut buffer, 7..14, 0);
+/// assert_eq!(&buffer[..7], b"payload");
+/// ```
+///
+/// # Panics
+///
+/// 1) If `src` or the destination region is out of the bounds of `buffer`.
+pub fn move_within(buffer: &mut [u8], src: Range<usize>, dest: usize) {
+    assert!(
+        src.start <= src.end && src.end <= buffer.len(),
+        "source range out of bounds"
+    );
+    let len = src.end - src.start;
+    assert!(dest <= buffer.len() - len, "destination out of bounds");
+    unsafe {
+        let base = buffer.as_mut_ptr();
+        raw::TEE_MemMove(base.add(dest) as _, base.add(src.start) as _, len as u32);
+    }
+}
+
+/// Fill the buffer with `value` using `TEE_MemFill`. Unlike a plain loop, the writes are not
+/// removed by the compiler when the buffer is not read afterwards, so it can be used to wipe
+/// secrets.
+///
+/// # Parameters
+///
+/// 1) `buffer`: The buffer to fill.
+/// 2) `value`: The byte written to every position of the buffer.
+pub fn secure_fill(buffer: &mut [u8], value: u8) {
+    unsafe {
+        raw::TEE_MemFill(buffer.as_mut_ptr() as _, value as u32, buffer.len() as u32);
+    }
+}
+
+/// Overwrite the buffer with zeros, see [secure_fill](secure_fill).
+pub fn secure_zero(buffer: &mut [u8]) {
+    secure_fill(buffer, 0);
+}
+
+/// Check that the Trusted Application has the `flags` access rights on the buffer, e.g. that a
+/// buffer given by the client lives in shared memory before trusting its content.
+///
+/// # Parameters
+///
+/// 1) `buffer`: The buffer to check.
+/// 2) `flags`: The required access rights.
+///
+/// # Example
+///
+/// ```no_run
+/// let mut memref = unsafe { params.0.as_memref()? };
+/// mem::check_access_rights(memref.buffer(), AccessFlags::READ | AccessFlags::ANY_OWNER)?;
+/// ```
+///
+/// # Errors
+///
+/// 1) `AccessDenied`: If at least one of the access rights is not granted on the whole buffer.
+pub fn check_access_rights(buffer: &[u8], flags: AccessFlags) -> Result<()> {
+    match unsafe {
+        raw::TEE_CheckMemoryAccessRights(
+            flags.bits(),
+            buffer.as_ptr() as *mut _,
+            buffer.len() as u32,
+        )
+    } {
+        raw::TEE_SUCCESS => Ok(()),
+        code => Err(Error::from_raw_error(code)),
+    }
+}
@@ -1,7 +1,11 @@
-use crate::{Error, Result};
+use crate::{Error, Result, SecretVec};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
 use bitflags::bitflags;
+use core::{marker, mem, ptr};
 use optee_utee_sys as raw;
-use std::{marker, mem, ptr};
+#[cfg(feature = "std")]
+use std::io;
 
 /// A general attribute (buffer or value) that can be used to populate an object or to specify
 /// opeation parameters.
@@ -237,6 +241,33 @@ impl ObjectHandle {
         }
     }
 
+    fn secret_attribute(&self, id: AttributeId) -> Result<SecretVec> {
+        // Query the size first so that the attribute is copied once, into the secret buffer.
+        let id = id as u32;
+        let mut size = 0u32;
+        match unsafe {
+            raw::TEE_GetObjectBufferAttribute(self.handle(), id, ptr::null_mut(), &mut size as _)
+        } {
+            raw::TEE_SUCCESS | raw::TEE_ERROR_SHORT_BUFFER => (),
+            code => return Err(Error::from_raw_error(code)),
+        }
+        let mut secret = SecretVec::zeroed(size as usize);
+        match unsafe {
+            raw::TEE_GetObjectBufferAttribute(
+                self.handle(),
+                id,
+                secret.expose_mut().as_mut_ptr() as _,
+                &mut size as _,
+            )
+        } {
+            raw::TEE_SUCCESS => {
+                secret.expose_mut().truncate(size as usize);
+                Ok(secret)
+            }
+            code => Err(Error::from_raw_error(code)),
+        }
+    }
+
     fn value_attribute(&self, id: u32) -> Result<(u32, u32)> {
         let mut value_a: u32 = 0;
         let mut value_b: u32 = 0;
@@ -390,6 +421,28 @@ pub enum AttributeId {
     EccCurve = 0xF0000441,
     BitProtected = 1 << 28,
     BitValue = 1 << 29,
+    /// HKDF input keying material: `IKM`
+    HkdfIkm = 0xC00001C0,
+    /// HKDF salt: `salt`
+    HkdfSalt = 0xD00002C0,
+    /// HKDF context and application specific information: `info`
+    HkdfInfo = 0xD00003C0,
+    /// HKDF output length in bytes: `L`
+    HkdfOkmLength = 0xF00004C0,
+    /// Concat KDF shared secret: `Z`
+    ConcatKdfZ = 0xC00001C1,
+    /// Concat KDF context information: `OtherInfo`
+    ConcatKdfOtherInfo = 0xD00002C1,
+    /// Concat KDF output length in bytes: `keydatalen`
+    ConcatKdfDkmLength = 0xF00003C1,
+    /// PBKDF2 password: `P`
+    Pbkdf2Password = 0xC00001C2,
+    /// PBKDF2 salt: `S`
+    Pbkdf2Salt = 0xD00002C2,
+    /// PBKDF2 iteration count: `c`
+    Pbkdf2IterationCount = 0xF00003C2,
+    /// PBKDF2 output length in bytes: `dkLen`
+    Pbkdf2DkmLength = 0xF00004C2,
 }
 
 /// Define types of [TransientObject](TransientObject) with predefined maximum sizes.
@@ -452,6 +505,12 @@ pub enum TransientObjectType {
     CorruptedObject = 0xA00000BE,
     /// 0 – All data is in the associated data stream.
     Data = 0xA00000BF,
+    /// Input keying material of [HKDF](../kdf/struct.Hkdf.html), up to 4096 bits (OP-TEE extension).
+    HkdfIkm = 0xA10000C0,
+    /// Shared secret of [Concat KDF](../kdf/struct.ConcatKdf.html), up to 4096 bits (OP-TEE extension).
+    ConcatKdfZ = 0xA10000C1,
+    /// Password of [PBKDF2](../kdf/struct.Pbkdf2.html), up to 4096 bits (OP-TEE extension).
+    Pbkdf2Password = 0xA10000C2,
 }
 
 /// A trait for an object (trasient or persistent) to return its handle.
@@ -672,6 +731,33 @@ impl TransientObject {
         self.0.ref_attribute(id, buffer)
     }
 
+    /// Extract one buffer attribute from an object into a [SecretVec](../secret/type.SecretVec.html),
+    /// which is wiped when it is dropped. The attribute is identified by the argument id.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `id`: Identifier of the attribute to retrieve.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let key = object.secret_attribute(AttributeId::SecretValue)?;
+    /// let attr = AttributeMemref::from_ref(AttributeId::SecretValue, key.expose());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) `ItemNotFound`: If the attribute is not found on this object.
+    ///
+    /// # Panics
+    ///
+    /// 1) If object is not a valid opened object.
+    /// 2) If the object is not initialized.
+    /// 3) If the Attribute is not a buffer attribute.
+    pub fn secret_attribute(&self, id: AttributeId) -> Result<SecretVec> {
+        self.0.secret_attribute(id)
+    }
+
     /// Extract one value attribute from an object. The attribute is identified by the argument id.
     ///
     /// # Parameters
@@ -1125,6 +1211,18 @@ impl PersistentObject {
         self.0.ref_attribute(id, buffer)
     }
 
+    /// Extract one buffer attribute from an object into a [SecretVec](../secret/type.SecretVec.html).
+    /// Function is similar to [TransientObject::secret_attribute](TransientObject::secret_attribute) besides extra errors.
+    ///
+    /// # Errors
+    ///
+    /// 1) `CorruptObject`: If the [PersistentObject](PersistentObject) is corrupt. The object handle is closed.
+    /// 2) `StorageNotAvailable`: If the [PersistentObject](PersistentObject) is stored in a storage area which is
+    ///    currently inaccessible.
+    pub fn secret_attribute(&self, id: AttributeId) -> Result<SecretVec> {
+        self.0.secret_attribute(id)
+    }
+
     /// Extract one value attribute from an object. The attribute is identified by the argument id.
     /// Function is similar to [TransientObject::value_attribute](TransientObject::value_attribute) besides extra errors.
     ///
@@ -1325,6 +1423,33 @@ impl ObjHandle for PersistentObject {
     }
 }
 
+/// Read the data stream of the object from the current data position, so that it can be chained
+/// with other readers such as [CipherReader](../stream/struct.CipherReader.html).
+#[cfg(feature = "std")]
+impl io::Read for PersistentObject {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match PersistentObject::read(self, buf) {
+            Ok(count) => Ok(count as usize),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Write to the data stream of the object at the current data position.
+#[cfg(feature = "std")]
+impl io::Write for PersistentObject {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match PersistentObject::write(self, buf) {
+            Ok(()) => Ok(buf.len()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 impl Drop for PersistentObject {
     /// Close an opened [PersistentObject](PersistentObject).
     ///
@@ -0,0 +1,12 @@
+use core::panic::PanicInfo;
+use optee_utee_sys as raw;
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    trace_println!("{}", info);
+    unsafe {
+        raw::TEE_Panic(raw::TEE_ERROR_GENERIC);
+    }
+    // `TEE_Panic` does not return.
+    loop {}
+}
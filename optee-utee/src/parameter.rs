@@ -1,7 +1,9 @@
+use crate::mem::{self, AccessFlags};
 use crate::{Error, ErrorKind, Result};
 use optee_utee_sys as raw;
-use std::marker;
-use std::convert::TryInto;
+use core::marker;
+use core::convert::TryInto;
+use alloc::vec::Vec;
 
 pub struct Parameters(pub Parameter, pub Parameter, pub Parameter, pub Parameter);
 
@@ -60,7 +62,7 @@ pub struct ParamMemref<'parameter> {
 impl<'parameter> ParamMemref<'parameter> {
     pub fn buffer(&mut self) -> &mut [u8] {
         unsafe {
-            std::slice::from_raw_parts_mut((*self.raw).buffer as *mut u8, (*self.raw).size as usize)
+            core::slice::from_raw_parts_mut((*self.raw).buffer as *mut u8, (*self.raw).size as usize)
         }
     }
 
@@ -68,6 +70,17 @@ impl<'parameter> ParamMemref<'parameter> {
         self.param_type
     }
 
+    /// Check that the Trusted Application has the `flags` access rights on the whole buffer, e.g.
+    /// `AccessFlags::READ | AccessFlags::ANY_OWNER | AccessFlags::SHARED` to make sure that the
+    /// buffer lives in memory shared with the client. See
+    /// [mem::check_access_rights](../mem/fn.check_access_rights.html).
+    pub fn check_access_rights(&self, flags: AccessFlags) -> Result<()> {
+        let buffer = unsafe {
+            core::slice::from_raw_parts((*self.raw).buffer as *const u8, (*self.raw).size as usize)
+        };
+        mem::check_access_rights(buffer, flags)
+    }
+
     pub fn raw(&mut self) -> *mut raw::Memref {
         self.raw
     }
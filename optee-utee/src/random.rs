@@ -0,0 +1,117 @@
+//! Typed helpers on top of [Random::generate](../crypto_op/struct.Random.html#method.generate),
+//! and the glue needed by the random number crates of the Rust ecosystem.
+//!
+//! With the `rand_core` feature, [TeeRng](TeeRng) implements `rand_core::RngCore` and
+//! `rand_core::CryptoRng`, so it can be given to crates generating keys, nonces or shuffles.
+//! With the `getrandom` feature, the [register_getrandom](../macro.register_getrandom.html)
+//! macro makes the `getrandom` crate, and every crate depending on it, use the TEE random number
+//! generator.
+
+use crate::Random;
+use core::ops::Range;
+
+/// Return an array filled with random bytes.
+///
+/// # Example
+///
+/// ```no_run
+/// let nonce: [u8; 12] = random::random_array();
+/// ```
+pub fn random_array<A: AsMut<[u8]> + Default>() -> A {
+    let mut array = A::default();
+    Random::generate(array.as_mut());
+    array
+}
+
+/// Return a random `u32`.
+pub fn random_u32() -> u32 {
+    u32::from_ne_bytes(random_array())
+}
+
+/// Return a random `u64`.
+pub fn random_u64() -> u64 {
+    u64::from_ne_bytes(random_array())
+}
+
+/// Return a uniformly distributed random number in `range`.
+///
+/// # Parameters
+///
+/// 1) `range`: The half-open range of the result, e.g. `0..6` for a dice roll.
+///
+/// # Example
+///
+/// ```no_run
+/// let roll = random::random_range(1..7);
+/// ```
+///
+/// # Panics
+///
+/// 1) If `range` is empty.
+pub fn random_range(range: Range<u64>) -> u64 {
+    assert!(range.start < range.end, "cannot sample an empty range");
+    let span = range.end - range.start;
+    // Values above the largest multiple of `span` are rejected, so that the remainder is not
+    // biased toward the lower numbers.
+    let rem = (u64::max_value() % span + 1) % span;
+    let limit = u64::max_value() - rem;
+    loop {
+        let value = random_u64();
+        if value <= limit {
+            return range.start + value % span;
+        }
+    }
+}
+
+/// A cryptographically secure random number generator backed by `TEE_GenerateRandom`.
+///
+/// The generator has no state, every instance draws from the random number generator of the
+/// TEE.
+///
+/// # Example
+///
+/// ```no_run
+/// use rand_core::RngCore;
+///
+/// let mut rng = TeeRng::new();
+/// let challenge = rng.next_u64();
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TeeRng;
+
+impl TeeRng {
+    /// Create a generator.
+    pub fn new() -> Self {
+        TeeRng
+    }
+}
+
+#[cfg(feature = "rand_core")]
+impl rand_core::RngCore for TeeRng {
+    fn next_u32(&mut self) -> u32 {
+        random_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        random_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        Random::generate(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        Random::generate(dest);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rand_core")]
+impl rand_core::CryptoRng for TeeRng {}
+
+#[doc(hidden)]
+#[cfg(feature = "getrandom")]
+pub unsafe fn _getrandom_custom(dest: *mut u8, len: usize) -> u32 {
+    Random::generate(core::slice::from_raw_parts_mut(dest, len));
+    0
+}
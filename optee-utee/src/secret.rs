@@ -0,0 +1,149 @@
+//! Containers for secrets, e.g. key material, which wipe their content when they are dropped so
+//! that it does not linger in the heap or the stack of the Trusted Application.
+
+use crate::mem;
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ptr;
+use core::sync::atomic::{self, Ordering};
+
+/// Types whose content can be securely overwritten with zeros.
+pub trait Zeroize {
+    /// Overwrite the content with zeros. The writes are not optimized away by the compiler even
+    /// if the value is never read again.
+    fn zeroize(&mut self);
+}
+
+impl Zeroize for [u8] {
+    fn zeroize(&mut self) {
+        for b in self.iter_mut() {
+            unsafe { ptr::write_volatile(b, 0) };
+        }
+        atomic::compiler_fence(Ordering::SeqCst);
+    }
+}
+
+/// The whole capacity of the vector is wiped, then the vector is cleared.
+impl Zeroize for Vec<u8> {
+    fn zeroize(&mut self) {
+        let ptr = self.as_mut_ptr();
+        for i in 0..self.capacity() {
+            unsafe { ptr::write_volatile(ptr.add(i), 0) };
+        }
+        atomic::compiler_fence(Ordering::SeqCst);
+        self.clear();
+    }
+}
+
+impl Zeroize for Box<[u8]> {
+    fn zeroize(&mut self) {
+        (**self).zeroize();
+    }
+}
+
+macro_rules! impl_zeroize_array {
+    ($($n:expr)*) => {
+        $(
+            impl Zeroize for [u8; $n] {
+                fn zeroize(&mut self) {
+                    self[..].zeroize();
+                }
+            }
+        )*
+    };
+}
+
+impl_zeroize_array! {
+    1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31 32
+    48 64 96 128 256 512
+}
+
+/// A secret value which is wiped when it is dropped.
+///
+/// The value is only reachable through [expose](Secret::expose) and
+/// [expose_mut](Secret::expose_mut), which makes the places where a secret is used easy to find.
+/// `Debug` does not print the value, and `==` runs in constant time for byte containers.
+///
+/// Only the memory owned by the `Secret` is wiped: a `Vec<u8>` which grows beyond its capacity
+/// leaves the old allocation behind, so [SecretVec](SecretVec) should be created with the final
+/// length, e.g. with [SecretVec::zeroed](Secret::zeroed).
+///
+/// # Example
+///
+/// ```no_run
+/// let mut key = Secret::new([0u8; 32]);
+/// Random::generate(key.expose_mut());
+/// let attr = AttributeMemref::from_ref(AttributeId::SecretValue, key.expose());
+/// object.populate(&[attr.into()])?;
+/// ```
+pub struct Secret<T: Zeroize> {
+    value: T,
+}
+
+/// A secret byte vector, e.g. as returned by
+/// [TransientObject::secret_attribute](../object/struct.TransientObject.html#method.secret_attribute).
+pub type SecretVec = Secret<Vec<u8>>;
+
+impl<T: Zeroize> Secret<T> {
+    /// Wrap a secret value.
+    pub fn new(value: T) -> Self {
+        Self { value }
+    }
+
+    /// Return a reference to the secret value.
+    pub fn expose(&self) -> &T {
+        &self.value
+    }
+
+    /// Return a mutable reference to the secret value.
+    pub fn expose_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl Secret<Vec<u8>> {
+    /// Create a secret vector of `len` zero bytes.
+    pub fn zeroed(len: usize) -> Self {
+        Self::new(vec![0u8; len])
+    }
+
+    /// Create a secret vector holding a copy of `bytes`.
+    pub fn from_slice(bytes: &[u8]) -> Self {
+        Self::new(bytes.to_vec())
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+impl<T: Zeroize> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: Zeroize + Default> Default for Secret<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Zeroize> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Secret([REDACTED])")
+    }
+}
+
+/// Compare the bytes in constant time, the lengths are not secret.
+impl<T: Zeroize + AsRef<[u8]>> PartialEq for Secret<T> {
+    fn eq(&self, other: &Self) -> bool {
+        mem::ct_eq(self.value.as_ref(), other.value.as_ref())
+    }
+}
+
+impl<T: Zeroize + AsRef<[u8]>> Eq for Secret<T> {}
@@ -0,0 +1,368 @@
+//! Adapters to drive crypto operations through `std::io` readers and writers.
+//!
+//! [CipherWriter](CipherWriter) and [CipherReader](CipherReader) only feed whole blocks to the
+//! underlying [Cipher](../crypto_op/struct.Cipher.html), so the output never lags behind by more
+//! than a block and no output buffer size has to be guessed by the caller. They can also add and
+//! remove PKCS#7 padding, which the `*Nopad` algorithms leave to the TA.
+//!
+//! # Example
+//!
+//! Decrypt a firmware image stored in secure storage and hash the plaintext on the fly:
+//!
+//! ```no_run
+//! let object = PersistentObject::open(ObjectStorageConstants::Private, b"firmware",
+//!     DataFlag::ACCESS_READ)?;
+//! let mut reader = CipherReader::new(cipher, &iv, Padding::Pkcs7, object)?;
+//! let mut hasher = DigestWriter::new(Digest::allocate(AlgorithmId::Sha256)?);
+//! std::io::copy(&mut reader, &mut hasher)?;
+//! let hash = hasher.finish()?;
+//! ```
+
+use crate::{AlgorithmId, Cipher, Digest, Error, ErrorKind, Mac, OperationMode, Result};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp;
+use std::io::{self, Read, Write};
+
+/// Size of the chunks read from the inner reader of a [CipherReader](CipherReader).
+const CHUNK_LEN: usize = 512;
+/// Largest digest or MAC produced by the supported algorithms (SHA-512).
+const MAX_DIGEST_LEN: usize = 64;
+
+/// Padding scheme applied by [CipherWriter](CipherWriter) and [CipherReader](CipherReader).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Padding {
+    /// No padding. For ECB and CBC modes the total input length must be a multiple of the block
+    /// size.
+    None,
+    /// PKCS#7 padding, added when encrypting and checked and removed when decrypting.
+    ///
+    /// The padding check is not constant time, so the ciphertext should be authenticated before
+    /// it is decrypted.
+    Pkcs7,
+}
+
+struct CipherStream {
+    cipher: Cipher,
+    padding: Padding,
+    decrypt: bool,
+    block_len: usize,
+    pending: Vec<u8>,
+}
+
+impl CipherStream {
+    fn new(cipher: Cipher, iv: &[u8], padding: Padding) -> Result<Self> {
+        let info = cipher.info();
+        let decrypt = info.mode() == Some(OperationMode::Decrypt);
+        let block_len = match info.algorithm() {
+            Some(AlgorithmId::AesEcbNopad)
+            | Some(AlgorithmId::AesCbcNopad)
+            | Some(AlgorithmId::AesCtr)
+            | Some(AlgorithmId::AesCts)
+            | Some(AlgorithmId::AesXts) => 16,
+            Some(AlgorithmId::DesEcbNopad)
+            | Some(AlgorithmId::DesCbcNopad)
+            | Some(AlgorithmId::Des3EcbNopad)
+            | Some(AlgorithmId::Des3CbcNopad) => 8,
+            _ => return Err(Error::new(ErrorKind::NotSupported)),
+        };
+        cipher.init(iv);
+        Ok(Self {
+            cipher,
+            padding,
+            decrypt,
+            block_len,
+            pending: Vec::new(),
+        })
+    }
+
+    fn strips_padding(&self) -> bool {
+        self.decrypt && self.padding == Padding::Pkcs7
+    }
+
+    fn update(&mut self, data: &[u8], out: &mut Vec<u8>) -> Result<()> {
+        self.pending.extend_from_slice(data);
+        // The last block is held back when decrypting so that the padding can be removed.
+        let keep = if self.strips_padding() {
+            self.block_len
+        } else {
+            0
+        };
+        if self.pending.len() <= keep {
+            return Ok(());
+        }
+        let len = (self.pending.len() - keep) / self.block_len * self.block_len;
+        if len == 0 {
+            return Ok(());
+        }
+        self.process(len, false, out)
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) -> Result<()> {
+        if self.padding == Padding::Pkcs7 {
+            if self.decrypt {
+                if self.pending.len() != self.block_len {
+                    return Err(Error::new(ErrorKind::BadFormat));
+                }
+            } else {
+                let pad = self.block_len - self.pending.len() % self.block_len;
+                let len = self.pending.len() + pad;
+                self.pending.resize(len, pad as u8);
+            }
+        }
+        let start = out.len();
+        let len = self.pending.len();
+        self.process(len, true, out)?;
+        if self.strips_padding() {
+            let plain = &out[start..];
+            let pad = match plain.last() {
+                Some(&pad) => pad as usize,
+                None => 0,
+            };
+            if pad == 0
+                || pad > self.block_len
+                || pad > plain.len()
+                || plain[plain.len() - pad..]
+                    .iter()
+                    .any(|&b| b as usize != pad)
+            {
+                out.truncate(start);
+                return Err(Error::new(ErrorKind::BadFormat));
+            }
+            let len = out.len() - pad;
+            out.truncate(len);
+        }
+        Ok(())
+    }
+
+    fn process(&mut self, len: usize, last: bool, out: &mut Vec<u8>) -> Result<()> {
+        let start = out.len();
+        out.resize(start + len + 2 * self.block_len, 0);
+        let res = if last {
+            self.cipher
+                .do_final(&self.pending[..len], &mut out[start..])
+        } else {
+            self.cipher.update(&self.pending[..len], &mut out[start..])
+        };
+        match res {
+            Ok(written) => {
+                out.truncate(start + written);
+                self.pending.drain(..len);
+                Ok(())
+            }
+            Err(e) => {
+                out.truncate(start);
+                Err(e)
+            }
+        }
+    }
+}
+
+/// A writer which encrypts or decrypts everything written to it with a [Cipher](Cipher) and
+/// writes the output to an inner writer.
+///
+/// The direction is the [OperationMode](OperationMode) the cipher was allocated with. Call
+/// [finish](CipherWriter::finish) once all data is written to process the last block.
+pub struct CipherWriter<W: Write> {
+    stream: CipherStream,
+    inner: W,
+}
+
+impl<W: Write> CipherWriter<W> {
+    /// Create a new writer and initialize the cipher operation.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `cipher`: A cipher operation with its key already set.
+    /// 2) `iv`: The Initialization Vector passed to [Cipher::init](Cipher::init).
+    /// 3) `padding`: The padding scheme.
+    /// 4) `inner`: The writer receiving the output.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let mut object = PersistentObject::create(ObjectStorageConstants::Private, b"data",
+    ///     DataFlag::ACCESS_WRITE, None, &[])?;
+    /// let mut writer = CipherWriter::new(cipher, &iv, Padding::Pkcs7, object)?;
+    /// writer.write_all(b"secret data")?;
+    /// writer.finish()?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// 1) `NotSupported`: If the algorithm of the operation is not an AES or DES cipher.
+    ///
+    /// # Panics
+    ///
+    /// 1) If no key is programmed in the operation.
+    /// 2) If the IV does not have the length required by the algorithm.
+    pub fn new(cipher: Cipher, iv: &[u8], padding: Padding, inner: W) -> Result<Self> {
+        Ok(Self {
+            stream: CipherStream::new(cipher, iv, padding)?,
+            inner,
+        })
+    }
+
+    /// Finalize the cipher operation, write the remaining output and return the inner writer.
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadFormat` (wrapped in an `io::Error`): If the padding of the decrypted data is
+    ///    invalid, or the total input length is not a multiple of the block size.
+    /// 2) Any error of the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        let mut out = Vec::new();
+        self.stream.finish(&mut out)?;
+        self.inner.write_all(&out)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    /// Return a reference to the inner writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: Write> Write for CipherWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut out = Vec::new();
+        self.stream.update(buf, &mut out)?;
+        self.inner.write_all(&out)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A reader which encrypts or decrypts the data read from an inner reader with a
+/// [Cipher](Cipher).
+///
+/// The cipher operation is finalized when the inner reader reaches its end, errors of the final
+/// block such as an invalid padding are returned by the last call to `read`.
+pub struct CipherReader<R: Read> {
+    stream: CipherStream,
+    inner: R,
+    out: Vec<u8>,
+    pos: usize,
+    eof: bool,
+}
+
+impl<R: Read> CipherReader<R> {
+    /// Create a new reader and initialize the cipher operation. Parameters and errors are the same
+    /// as [CipherWriter::new](CipherWriter::new).
+    pub fn new(cipher: Cipher, iv: &[u8], padding: Padding, inner: R) -> Result<Self> {
+        Ok(Self {
+            stream: CipherStream::new(cipher, iv, padding)?,
+            inner,
+            out: Vec::new(),
+            pos: 0,
+            eof: false,
+        })
+    }
+
+    /// Return the inner reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for CipherReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.out.len() && !self.eof {
+            self.out.clear();
+            self.pos = 0;
+            let mut chunk = [0u8; CHUNK_LEN];
+            let n = self.inner.read(&mut chunk)?;
+            if n == 0 {
+                self.eof = true;
+                self.stream.finish(&mut self.out)?;
+            } else {
+                self.stream.update(&chunk[..n], &mut self.out)?;
+            }
+        }
+        let n = cmp::min(buf.len(), self.out.len() - self.pos);
+        buf[..n].copy_from_slice(&self.out[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// A writer which hashes everything written to it with a [Digest](Digest).
+pub struct DigestWriter {
+    digest: Digest,
+}
+
+impl DigestWriter {
+    /// Create a new writer from an allocated digest operation.
+    pub fn new(digest: Digest) -> Self {
+        Self { digest }
+    }
+
+    /// Finalize the digest operation and return the message hash.
+    pub fn finish(self) -> Result<Vec<u8>> {
+        let mut hash = vec![0u8; MAX_DIGEST_LEN];
+        let len = self.digest.do_final(&[], &mut hash)?;
+        hash.truncate(len);
+        Ok(hash)
+    }
+}
+
+impl Write for DigestWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.digest.try_update(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A writer which computes the MAC of everything written to it with a [Mac](Mac).
+pub struct MacWriter {
+    mac: Mac,
+}
+
+impl MacWriter {
+    /// Create a new writer and initialize the MAC operation.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `mac`: A MAC operation with its key already set.
+    /// 2) `iv`: The Initialization Vector passed to [Mac::init](Mac::init), if applicable.
+    pub fn new(mac: Mac, iv: &[u8]) -> Self {
+        mac.init(iv);
+        Self { mac }
+    }
+
+    /// Finalize the MAC operation and return the computed MAC.
+    pub fn compute_final(self) -> Result<Vec<u8>> {
+        let mut mac = vec![0u8; MAX_DIGEST_LEN];
+        let len = self.mac.compute_final(&[], &mut mac)?;
+        mac.truncate(len);
+        Ok(mac)
+    }
+
+    /// Finalize the MAC operation and compare the MAC with `mac`.
+    ///
+    /// # Errors
+    ///
+    /// 1) `MacInvalid`: If the computed MAC does not correspond to `mac`.
+    pub fn compare_final(self, mac: &[u8]) -> Result<()> {
+        self.mac.compare_final(&[], mac)
+    }
+}
+
+impl Write for MacWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.mac.try_update(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,133 @@
+//! A trait-based definition of a Trusted Application, as an alternative to the entry point
+//! attributes such as `#[ta_create]` and `#[ta_invoke_command]`.
+//!
+//! The Trusted Application implements [TrustedApplication](TrustedApplication) and its session
+//! type implements [TaSession](TaSession), then [ta_main!](../macro.ta_main.html) generates all
+//! the entry points:
+//!
+//! ```no_run
+//! struct Counter {
+//!     value: u32,
+//! }
+//!
+//! impl TaSession for Counter {
+//!     fn open(_params: &mut Parameters) -> Result<Self> {
+//!         Ok(Counter { value: 0 })
+//!     }
+//!
+//!     fn invoke(&mut self, cmd_id: u32, params: &mut Parameters) -> Result<()> {
+//!         let mut values = unsafe { params.0.as_value()? };
+//!         self.value += values.a();
+//!         values.set_a(self.value);
+//!         Ok(())
+//!     }
+//! }
+//!
+//! struct CounterTa;
+//!
+//! impl TrustedApplication for CounterTa {
+//!     type Session = Counter;
+//! }
+//!
+//! ta_main!(CounterTa);
+//! ```
+
+use crate::{Parameters, Result};
+use alloc::boxed::Box;
+use core::ffi::c_void;
+use optee_utee_sys as raw;
+
+#[doc(hidden)]
+pub use optee_utee_sys::{TEE_Param as _Param, TEE_Result as _Result};
+
+/// The entry points of a Trusted Application instance.
+pub trait TrustedApplication {
+    /// The state of a session, created when a client opens a session and dropped when the
+    /// session is closed.
+    type Session: TaSession;
+
+    /// Called when the instance is created, before the first session is opened. An error
+    /// aborts the creation of the instance and is returned to the client opening the session.
+    fn create() -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when the instance is destroyed, after the last session is closed.
+    fn destroy() {}
+}
+
+/// A session of a [TrustedApplication](TrustedApplication).
+pub trait TaSession: Sized {
+    /// Create the session from the parameters given by the client to open the session. An
+    /// error is returned to the client and no session is created.
+    fn open(params: &mut Parameters) -> Result<Self>;
+
+    /// Handle the command `cmd_id` invoked by the client.
+    fn invoke(&mut self, cmd_id: u32, params: &mut Parameters) -> Result<()>;
+
+    /// Called when the client closes the session. The session is dropped afterwards.
+    fn close(self) {}
+}
+
+#[doc(hidden)]
+pub fn _create<T: TrustedApplication>() -> raw::TEE_Result {
+    match T::create() {
+        Ok(_) => raw::TEE_SUCCESS,
+        Err(e) => e.raw_code(),
+    }
+}
+
+#[doc(hidden)]
+pub fn _destroy<T: TrustedApplication>() {
+    T::destroy();
+}
+
+#[doc(hidden)]
+pub unsafe fn _open_session<T: TrustedApplication>(
+    param_types: u32,
+    params: &mut [raw::TEE_Param; 4],
+    sess_ctx: *mut *mut c_void,
+) -> raw::TEE_Result {
+    let mut parameters = Parameters::from_raw(params, param_types);
+    match T::Session::open(&mut parameters) {
+        Ok(session) => {
+            *sess_ctx = Box::into_raw(Box::new(session)) as _;
+            raw::TEE_SUCCESS
+        }
+        Err(e) => e.raw_code(),
+    }
+}
+
+#[doc(hidden)]
+pub unsafe fn _close_session<T: TrustedApplication>(sess_ctx: *mut c_void) {
+    if sess_ctx.is_null() {
+        panic!("sess_ctx is null");
+    }
+    let session = Box::from_raw(sess_ctx as *mut T::Session);
+    session.close();
+}
+
+#[doc(hidden)]
+pub unsafe fn _invoke_command<T: TrustedApplication>(
+    sess_ctx: *mut c_void,
+    cmd_id: u32,
+    param_types: u32,
+    params: &mut [raw::TEE_Param; 4],
+) -> raw::TEE_Result {
+    if sess_ctx.is_null() {
+        return raw::TEE_ERROR_SECURITY;
+    }
+    let mut parameters = Parameters::from_raw(params, param_types);
+    if let Some(res) = crate::trace::_handle_capture_command(cmd_id, &mut parameters) {
+        return match res {
+            Ok(_) => raw::TEE_SUCCESS,
+            Err(e) => e.raw_code(),
+        };
+    }
+    // The session stays owned by the framework until it is closed, whatever the result.
+    let session = &mut *(sess_ctx as *mut T::Session);
+    match session.invoke(cmd_id, &mut parameters) {
+        Ok(_) => raw::TEE_SUCCESS,
+        Err(e) => e.raw_code(),
+    }
+}
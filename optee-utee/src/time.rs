@@ -1,8 +1,15 @@
-use crate::{Error, Result};
+use crate::{DataFlag, Error, ErrorKind, ObjectStorageConstants, PersistentObject, Result};
+use alloc::vec::Vec;
+use core::cmp;
+use core::convert::TryFrom;
+use core::fmt;
+use core::ops::{Add, AddAssign, Sub, SubAssign};
+use core::time::Duration;
 use optee_utee_sys as raw;
-use std::fmt;
 
 /// A millisecond resolution structure for saving the time.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct Time {
     /// The field for the seconds.
     pub seconds: u32,
@@ -41,17 +48,18 @@ impl Time {
         }
     }
 
-    /// Wait  for the specified number of milliseconds or wait forever if timeout equals
-    /// `raw::TEE_TIMEOUT_INFINITE` (0xFFFFFFFF). The waiting timer is `System Time`.
+    /// Wait for the specified duration, with a millisecond resolution. A duration of
+    /// `raw::TEE_TIMEOUT_INFINITE` (0xFFFFFFFF) milliseconds or more waits forever. The waiting
+    /// timer is `System Time`.
     ///
     /// # Parameters
     ///
-    /// 1) `timeout`: The number of milliseconds to wait, or `raw::TEE_TIMEOUT_INFINITE`.
+    /// 1) `timeout`: The duration to wait.
     ///
     /// # Example
     ///
     /// ```no_run
-    /// Time::wait(1000)?;
+    /// Time::wait(Duration::from_secs(1))?;
     /// ```
     ///
     /// # Errors
@@ -61,8 +69,13 @@ impl Time {
     /// # Panics
     ///
     /// 1) If the Implementation detects any error.
-
-    pub fn wait(timeout: u32) -> Result<()> {
+    pub fn wait(timeout: Duration) -> Result<()> {
+        let millis = timeout.as_secs().saturating_mul(1000) + u64::from(timeout.subsec_millis());
+        let timeout = if millis >= u64::from(raw::TEE_TIMEOUT_INFINITE) {
+            raw::TEE_TIMEOUT_INFINITE
+        } else {
+            millis as u32
+        };
         match unsafe { raw::TEE_Wait(timeout) } {
             raw::TEE_SUCCESS => Ok(()),
             code => Err(Error::from_raw_error(code)),
@@ -100,6 +113,36 @@ impl Time {
         }
     }
 
+    /// Retrieve the persistent time of the Trusted Application, with the errors specific to the
+    /// persistent time reported as a [TaTimeError](TaTimeError).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let time = match Time::persistent_time() {
+    ///     Ok(time) => time,
+    ///     Err(TaTimeError::NotSet) | Err(TaTimeError::NeedsReset) => {
+    ///         // Re-synchronize the time from a trusted source before setting it.
+    ///         let time = trusted_source_time()?;
+    ///         time.set_ta_time()?;
+    ///         time
+    ///     }
+    ///     Err(e) => return Err(e.into()),
+    /// };
+    /// ```
+    pub fn persistent_time() -> core::result::Result<Self, TaTimeError> {
+        let mut time = Self::new();
+        match time.ta_time() {
+            Ok(()) => Ok(time),
+            Err(e) => Err(match e.kind() {
+                ErrorKind::TimeNotSet => TaTimeError::NotSet,
+                ErrorKind::TimeNeedsReset => TaTimeError::NeedsReset,
+                ErrorKind::Overflow => TaTimeError::Overflow(time),
+                _ => TaTimeError::Other(e),
+            }),
+        }
+    }
+
     /// Set the persistent time of the current Trusted Application.
     ///
     /// # Errors
@@ -128,6 +171,48 @@ impl Time {
             raw::TEE_GetREETime(self as *mut _ as _);
         }
     }
+
+    /// Return `self + duration`, or `None` if the seconds overflow a `u32`.
+    pub fn checked_add(&self, duration: Duration) -> Option<Time> {
+        Duration::from(*self)
+            .checked_add(duration)
+            .and_then(|d| Time::try_from(d).ok())
+    }
+
+    /// Return `self - duration`, or `None` if the result would be negative.
+    pub fn checked_sub(&self, duration: Duration) -> Option<Time> {
+        Duration::from(*self)
+            .checked_sub(duration)
+            .and_then(|d| Time::try_from(d).ok())
+    }
+
+    /// Return the duration elapsed from `earlier` to `self`, or `None` if `earlier` is later
+    /// than `self`.
+    pub fn checked_duration_since(&self, earlier: Time) -> Option<Duration> {
+        Duration::from(*self).checked_sub(Duration::from(earlier))
+    }
+}
+
+impl From<Time> for Duration {
+    fn from(time: Time) -> Duration {
+        Duration::from_secs(u64::from(time.seconds)) + Duration::from_millis(u64::from(time.millis))
+    }
+}
+
+/// Convert a duration since the time origin, truncated to the millisecond. Fail with `Overflow`
+/// if the seconds do not fit in a `u32`.
+impl TryFrom<Duration> for Time {
+    type Error = Error;
+
+    fn try_from(duration: Duration) -> Result<Time> {
+        if duration.as_secs() > u64::from(u32::max_value()) {
+            return Err(Error::new(ErrorKind::Overflow));
+        }
+        Ok(Time {
+            seconds: duration.as_secs() as u32,
+            millis: duration.subsec_millis(),
+        })
+    }
 }
 
 impl fmt::Display for Time {
@@ -139,3 +224,433 @@ impl fmt::Display for Time {
         )
     }
 }
+
+/// Errors of the TA persistent time returned by [Time::persistent_time](Time::persistent_time).
+#[derive(Debug)]
+pub enum TaTimeError {
+    /// The persistent time has never been set by this Trusted Application.
+    NotSet,
+    /// The persistent time has been set but may have been corrupted or rolled back, it must no
+    /// longer be trusted and has to be set again.
+    NeedsReset,
+    /// The number of seconds overflows a `u32`, the time is truncated to 32 bits.
+    Overflow(Time),
+    /// Any other error reported by the implementation.
+    Other(Error),
+}
+
+impl fmt::Display for TaTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self {
+            TaTimeError::NotSet => ErrorKind::TimeNotSet,
+            TaTimeError::NeedsReset => ErrorKind::TimeNeedsReset,
+            TaTimeError::Overflow(_) => ErrorKind::Overflow,
+            TaTimeError::Other(e) => return write!(f, "{}", e),
+        };
+        write!(f, "{}", Error::new(kind))
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TaTimeError {}
+
+impl From<TaTimeError> for Error {
+    fn from(e: TaTimeError) -> Error {
+        match e {
+            TaTimeError::NotSet => Error::new(ErrorKind::TimeNotSet),
+            TaTimeError::NeedsReset => Error::new(ErrorKind::TimeNeedsReset),
+            TaTimeError::Overflow(_) => Error::new(ErrorKind::Overflow),
+            TaTimeError::Other(e) => e,
+        }
+    }
+}
+
+/// A measurement of the monotonic system time, similar to `std::time::Instant`.
+///
+/// Instants are taken from [Time::system_time](Time::system_time), so they are only comparable
+/// within the same TA instance.
+///
+/// # Example
+///
+/// ```no_run
+/// let start = Instant::now();
+/// Time::wait(Duration::from_millis(100))?;
+/// assert!(start.elapsed() >= Duration::from_millis(100));
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Instant(Duration);
+
+impl Instant {
+    /// Return an instant corresponding to now.
+    pub fn now() -> Instant {
+        let mut time = Time::new();
+        time.system_time();
+        Instant(time.into())
+    }
+
+    /// Return the duration elapsed since this instant was created.
+    pub fn elapsed(&self) -> Duration {
+        Instant::now().duration_since(*self)
+    }
+
+    /// Return the duration elapsed from `earlier` to `self`, or zero if `earlier` is later than
+    /// `self`.
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        self.checked_duration_since(earlier)
+            .unwrap_or_else(|| Duration::from_secs(0))
+    }
+
+    /// Return the duration elapsed from `earlier` to `self`, or `None` if `earlier` is later
+    /// than `self`.
+    pub fn checked_duration_since(&self, earlier: Instant) -> Option<Duration> {
+        self.0.checked_sub(earlier.0)
+    }
+
+    /// Return `self + duration`, or `None` on overflow.
+    pub fn checked_add(&self, duration: Duration) -> Option<Instant> {
+        self.0.checked_add(duration).map(Instant)
+    }
+
+    /// Return `self - duration`, or `None` if the result would be before the time origin.
+    pub fn checked_sub(&self, duration: Duration) -> Option<Instant> {
+        self.0.checked_sub(duration).map(Instant)
+    }
+}
+
+/// Panics on overflow, use [checked_add](Instant::checked_add) to avoid it.
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, duration: Duration) -> Instant {
+        self.checked_add(duration)
+            .expect("overflow when adding duration to instant")
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, duration: Duration) {
+        *self = *self + duration;
+    }
+}
+
+/// Panics if the result would be before the time origin, use
+/// [checked_sub](Instant::checked_sub) to avoid it.
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+
+    fn sub(self, duration: Duration) -> Instant {
+        self.checked_sub(duration)
+            .expect("overflow when subtracting duration from instant")
+    }
+}
+
+impl SubAssign<Duration> for Instant {
+    fn sub_assign(&mut self, duration: Duration) {
+        *self = *self - duration;
+    }
+}
+
+/// Same as [duration_since](Instant::duration_since).
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, earlier: Instant) -> Duration {
+        self.duration_since(earlier)
+    }
+}
+
+/// Identifier of the secure storage object holding the state of [TrustedClock](TrustedClock).
+const TRUSTED_CLOCK_OBJECT_ID: &[u8] = b"optee_utee::time::TrustedClock";
+
+/// A wall-clock estimate anchored on the TA persistent time, which never goes backwards.
+///
+/// When anchored, the clock reads the TA persistent time and compares it with a checkpoint kept
+/// in secure storage. If the persistent time is not set, needs to be reset, or is earlier than
+/// the checkpoint, it is reset from the REE time but never before the checkpoint, and the reset
+/// [generation](TrustedClock::generation) is incremented. The clock then advances with the
+/// system time, which is monotonic for the life of the TA instance.
+///
+/// Time elapsed across a reset cannot be trusted, consumers such as
+/// [RateLimiter](RateLimiter) should not credit it when the generation changes. The checkpoint is
+/// only as rollback resistant as the secure storage it is kept in.
+///
+/// # Example
+///
+/// ```no_run
+/// let clock = TrustedClock::anchor()?;
+/// if clock.was_reset() {
+///     trace_println!("[!] Persistent time has been reset.");
+/// }
+/// let now: Duration = clock.now();
+/// ```
+pub struct TrustedClock {
+    base: Duration,
+    anchor: Instant,
+    generation: u32,
+    reset: bool,
+}
+
+impl TrustedClock {
+    /// Anchor the clock on the TA persistent time and update the checkpoint in secure storage.
+    ///
+    /// # Errors
+    ///
+    /// 1) `StorageNoSpace`, `CorruptObject` or `StorageNotAvailable`: If the checkpoint cannot be
+    ///    read or written.
+    /// 2) `Overflow`: If the persistent time overflows the range of a `u32`.
+    pub fn anchor() -> Result<Self> {
+        let mut state = [0u8; 12];
+        let stored = load_state(TRUSTED_CLOCK_OBJECT_ID, &mut state)?;
+        let mut generation = if stored { be_u32(&state[..4]) } else { 0 };
+        let checkpoint = Duration::from_millis(be_u64(&state[4..]));
+        let persistent = match Time::persistent_time() {
+            Ok(time) if Duration::from(time) >= checkpoint => Some(Duration::from(time)),
+            Ok(_) | Err(TaTimeError::NotSet) | Err(TaTimeError::NeedsReset) => None,
+            Err(e) => return Err(e.into()),
+        };
+        let (base, reset) = match persistent {
+            Some(time) => (time, false),
+            None => {
+                let mut ree = Time::new();
+                ree.ree_time();
+                let time = cmp::max(Duration::from(ree), checkpoint);
+                Time::try_from(time)?.set_ta_time()?;
+                generation = generation.wrapping_add(1);
+                (time, true)
+            }
+        };
+        state[..4].copy_from_slice(&generation.to_be_bytes());
+        state[4..].copy_from_slice(&duration_millis(base).to_be_bytes());
+        store_state(TRUSTED_CLOCK_OBJECT_ID, &state)?;
+        Ok(Self {
+            base,
+            anchor: Instant::now(),
+            generation,
+            reset,
+        })
+    }
+
+    /// Return the current time since the origin of the TA persistent time.
+    pub fn now(&self) -> Duration {
+        self.base + self.anchor.elapsed()
+    }
+
+    /// Return `true` if the persistent time had to be reset when this clock was anchored.
+    pub fn was_reset(&self) -> bool {
+        self.reset
+    }
+
+    /// Return the number of resets of the persistent time detected so far.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Save the current persistent time as the checkpoint, so that a later rollback of the
+    /// persistent time to before now is detected.
+    ///
+    /// # Errors
+    ///
+    /// 1) `TimeNotSet` or `TimeNeedsReset`: If the persistent time has been reset since the clock
+    ///    was anchored, a new clock has to be anchored.
+    /// 2) Errors of the secure storage, e.g. `StorageNoSpace`.
+    pub fn checkpoint(&self) -> Result<()> {
+        // The persistent time is used rather than `now`, which may drift from it with the system
+        // time and would make the next anchor detect a rollback.
+        let time = cmp::max(Duration::from(Time::persistent_time()?), self.base);
+        let mut state = [0u8; 12];
+        state[..4].copy_from_slice(&self.generation.to_be_bytes());
+        state[4..].copy_from_slice(&duration_millis(time).to_be_bytes());
+        store_state(TRUSTED_CLOCK_OBJECT_ID, &state)
+    }
+}
+
+/// A token bucket rate limiter whose state is kept in secure storage, so that it survives
+/// reboots and TA restarts.
+///
+/// Each attempt consumes a token, and a token is given back every `refill_interval` up to the
+/// capacity of the bucket. Without a refill interval, the limiter is a plain attempt counter
+/// which only [reset](RateLimiter::reset) restores. Time elapsed across a reset of the persistent
+/// time, as reported by the [generation](TrustedClock::generation) of the clock, does not refill
+/// the bucket.
+///
+/// # Example
+///
+/// Allow 3 PIN attempts, then one more every 10 minutes:
+///
+/// ```no_run
+/// let clock = TrustedClock::anchor()?;
+/// let mut limiter = RateLimiter::open(b"pin", 3, Some(Duration::from_secs(600)))?;
+/// // The attempt is recorded before the PIN is checked, so cutting the power during the check
+/// // does not give a free attempt.
+/// limiter.try_acquire(&clock)?;
+/// if mem::ct_eq(&pin, &expected_pin) {
+///     limiter.reset(&clock)?;
+/// }
+/// ```
+pub struct RateLimiter {
+    object_id: Vec<u8>,
+    capacity: u32,
+    refill_interval: Option<Duration>,
+    tokens: u32,
+    generation: u32,
+    last_refill: Duration,
+}
+
+impl RateLimiter {
+    /// Open the limiter stored under `object_id`, or create it with a full bucket.
+    ///
+    /// # Parameters
+    ///
+    /// 1) `object_id`: The identifier of the secure storage object holding the state.
+    /// 2) `capacity`: The maximum number of attempts available at once.
+    /// 3) `refill_interval`: The time to give back one attempt, or `None` to never give any back.
+    ///
+    /// # Errors
+    ///
+    /// 1) `BadParameters`: If `capacity` is zero or `refill_interval` is zero.
+    /// 2) Errors of the secure storage, e.g. `CorruptObject` or `StorageNotAvailable`.
+    pub fn open(
+        object_id: &[u8],
+        capacity: u32,
+        refill_interval: Option<Duration>,
+    ) -> Result<Self> {
+        if capacity == 0 || refill_interval == Some(Duration::from_secs(0)) {
+            return Err(Error::new(ErrorKind::BadParameters));
+        }
+        let mut state = [0u8; 16];
+        let stored = load_state(object_id, &mut state)?;
+        let mut limiter = Self {
+            object_id: object_id.to_vec(),
+            capacity,
+            refill_interval,
+            tokens: capacity,
+            generation: 0,
+            last_refill: Duration::from_secs(0),
+        };
+        if stored {
+            limiter.tokens = cmp::min(be_u32(&state[..4]), capacity);
+            limiter.generation = be_u32(&state[4..8]);
+            limiter.last_refill = Duration::from_millis(be_u64(&state[8..]));
+        }
+        Ok(limiter)
+    }
+
+    /// Return the number of attempts currently available.
+    pub fn remaining(&mut self, clock: &TrustedClock) -> u32 {
+        self.refill(clock);
+        self.tokens
+    }
+
+    /// Return how long to wait before the next attempt is available, zero if one is available
+    /// now, or `None` if the bucket is empty and never refilled.
+    pub fn retry_after(&mut self, clock: &TrustedClock) -> Option<Duration> {
+        self.refill(clock);
+        if self.tokens > 0 {
+            return Some(Duration::from_secs(0));
+        }
+        self.refill_interval.map(|interval| {
+            (self.last_refill + interval)
+                .checked_sub(clock.now())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Consume an attempt. The new state is written to secure storage before returning.
+    ///
+    /// # Errors
+    ///
+    /// 1) `AccessDenied`: If no attempt is available.
+    /// 2) Errors of the secure storage, in which case the attempt must be denied too.
+    pub fn try_acquire(&mut self, clock: &TrustedClock) -> Result<()> {
+        self.refill(clock);
+        if self.tokens == 0 {
+            return Err(Error::new(ErrorKind::AccessDenied));
+        }
+        self.tokens -= 1;
+        self.store()
+    }
+
+    /// Refill the bucket to its capacity, e.g. after a successful authentication.
+    pub fn reset(&mut self, clock: &TrustedClock) -> Result<()> {
+        self.tokens = self.capacity;
+        self.generation = clock.generation();
+        self.last_refill = clock.now();
+        self.store()
+    }
+
+    fn refill(&mut self, clock: &TrustedClock) {
+        let now = clock.now();
+        if self.generation != clock.generation() {
+            // Time elapsed across a reset of the persistent time cannot be trusted.
+            self.generation = clock.generation();
+            self.last_refill = now;
+            return;
+        }
+        let interval = match self.refill_interval {
+            Some(interval) => interval,
+            None => return,
+        };
+        if self.tokens >= self.capacity {
+            self.last_refill = now;
+            return;
+        }
+        let elapsed = duration_millis(now.checked_sub(self.last_refill).unwrap_or_default());
+        let count = elapsed / duration_millis(interval);
+        if count >= u64::from(self.capacity - self.tokens) {
+            self.tokens = self.capacity;
+            self.last_refill = now;
+        } else {
+            self.tokens += count as u32;
+            self.last_refill += Duration::from_millis(count * duration_millis(interval));
+        }
+    }
+
+    fn store(&self) -> Result<()> {
+        let mut state = [0u8; 16];
+        state[..4].copy_from_slice(&self.tokens.to_be_bytes());
+        state[4..8].copy_from_slice(&self.generation.to_be_bytes());
+        state[8..].copy_from_slice(&duration_millis(self.last_refill).to_be_bytes());
+        store_state(&self.object_id, &state)
+    }
+}
+
+fn duration_millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0, |acc, &b| (acc << 8) | u32::from(b))
+}
+
+fn be_u64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |acc, &b| (acc << 8) | u64::from(b))
+}
+
+// Read a fixed size state from secure storage, return `false` if it does not exist yet.
+fn load_state(object_id: &[u8], state: &mut [u8]) -> Result<bool> {
+    let object = match PersistentObject::open(
+        ObjectStorageConstants::Private,
+        object_id,
+        DataFlag::ACCESS_READ | DataFlag::SHARE_READ,
+    ) {
+        Ok(object) => object,
+        Err(ref e) if e.kind() == ErrorKind::ItemNotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    if object.read(state)? as usize != state.len() {
+        return Err(Error::new(ErrorKind::CorruptObject));
+    }
+    Ok(true)
+}
+
+// Atomically replace a state in secure storage.
+fn store_state(object_id: &[u8], state: &[u8]) -> Result<()> {
+    PersistentObject::create(
+        ObjectStorageConstants::Private,
+        object_id,
+        DataFlag::ACCESS_WRITE | DataFlag::OVERWRITE,
+        None,
+        state,
+    )?;
+    Ok(())
+}
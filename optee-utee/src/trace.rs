@@ -1,8 +1,99 @@
+use crate::{Parameters, Result};
+use core::fmt;
+use core::fmt::Write;
 use libc;
 use optee_utee_sys as raw;
-use std::fmt;
+#[cfg(feature = "std")]
 use std::io;
-use std::io::Write;
+
+/// The trace levels of OP-TEE.
+///
+/// A message is printed if its level is lower than or equal to both the compile-time
+/// [MAX_LEVEL](MAX_LEVEL) and the runtime level set by [Trace::set_level](Trace::set_level).
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum TraceLevel {
+    Error = 1,
+    Info = 2,
+    Debug = 3,
+    Flow = 4,
+}
+
+/// The highest level compiled in by [trace_error!](../macro.trace_error.html) and the other leveled
+/// macros, and printed by [TraceLogger](TraceLogger), as an `i32` like the `TRACE_LEVEL` of the
+/// Trusted Application.
+///
+/// It is `Flow` by default, and is lowered with the `trace_level_off`, `trace_level_error`,
+/// `trace_level_info` and `trace_level_debug` features, the lowest one wins. The runtime level of
+/// a Trusted Application starts at its `TRACE_LEVEL`, so enabling the feature which matches
+/// `TRACE_LEVEL` compiles out the messages which would never be printed.
+pub const MAX_LEVEL: i32 = max_level();
+
+#[cfg(feature = "trace_level_off")]
+const fn max_level() -> i32 {
+    0
+}
+
+#[cfg(all(feature = "trace_level_error", not(feature = "trace_level_off")))]
+const fn max_level() -> i32 {
+    TraceLevel::Error as i32
+}
+
+#[cfg(all(
+    feature = "trace_level_info",
+    not(any(feature = "trace_level_off", feature = "trace_level_error"))
+))]
+const fn max_level() -> i32 {
+    TraceLevel::Info as i32
+}
+
+#[cfg(all(
+    feature = "trace_level_debug",
+    not(any(
+        feature = "trace_level_off",
+        feature = "trace_level_error",
+        feature = "trace_level_info"
+    ))
+))]
+const fn max_level() -> i32 {
+    TraceLevel::Debug as i32
+}
+
+#[cfg(not(any(
+    feature = "trace_level_off",
+    feature = "trace_level_error",
+    feature = "trace_level_info",
+    feature = "trace_level_debug"
+)))]
+const fn max_level() -> i32 {
+    TraceLevel::Flow as i32
+}
+
+impl TraceLevel {
+    /// The prefix printed by OP-TEE for this level.
+    pub fn prefix(self) -> &'static str {
+        match self {
+            TraceLevel::Error => "ERR",
+            TraceLevel::Info => "INF",
+            TraceLevel::Debug => "DBG",
+            TraceLevel::Flow => "FLW",
+        }
+    }
+
+    /// Return `true` if messages of this level are enabled by the runtime level.
+    pub fn is_enabled(self) -> bool {
+        self as i32 <= Trace::get_level()
+    }
+}
+
+/// The command identifier reserved by the `trace_capture` feature.
+///
+/// When the feature is enabled, the messages printed to the trace output are also kept in a
+/// buffer of the TA instance, and invoking this command returns and clears them. The command is
+/// handled before the `#[ta_invoke_command]` function is called, so Trusted Applications must not
+/// use this identifier for their own commands. The host side is
+/// `optee_teec::Session::take_trace_records`.
+pub const CAPTURE_COMMAND_ID: u32 = 0xFFFF_FFF0;
 
 pub struct Trace;
 
@@ -12,6 +103,27 @@ impl Trace {
     }
 
     pub fn _print(fmt: fmt::Arguments) {
+        Trace::output(fmt);
+        #[cfg(feature = "trace_capture")]
+        capture::push(None, None, fmt);
+    }
+
+    #[doc(hidden)]
+    pub fn _print_level(level: TraceLevel, file: &str, line: u32, fmt: fmt::Arguments) {
+        if level.is_enabled() {
+            Trace::output(format_args!(
+                "{} {}:{}: {}\n",
+                level.prefix(),
+                file,
+                line,
+                fmt
+            ));
+            #[cfg(feature = "trace_capture")]
+            capture::push(Some(level), Some((file, line)), fmt);
+        }
+    }
+
+    fn output(fmt: fmt::Arguments) {
         let mut writer = Trace::new();
         let result = writer.write_fmt(fmt);
 
@@ -20,6 +132,12 @@ impl Trace {
         }
     }
 
+    fn log(buf: &[u8]) {
+        unsafe {
+            raw::utee_log(buf.as_ptr() as *const libc::c_void, buf.len());
+        }
+    }
+
     pub fn set_level(level: i32) {
         unsafe {
             raw::trace_set_level(level);
@@ -31,11 +149,17 @@ impl Trace {
     }
 }
 
+impl fmt::Write for Trace {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        Trace::log(s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
 impl io::Write for Trace {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        unsafe {
-            raw::utee_log(buf.as_ptr() as *const libc::c_void, buf.len());
-        }
+        Trace::log(buf);
         Ok(buf.len())
     }
 
@@ -43,3 +167,192 @@ impl io::Write for Trace {
         Ok(())
     }
 }
+
+#[doc(hidden)]
+#[cfg(feature = "trace_capture")]
+pub fn _handle_capture_command(cmd_id: u32, params: &mut Parameters) -> Option<Result<()>> {
+    if cmd_id == CAPTURE_COMMAND_ID {
+        Some(capture::drain(params))
+    } else {
+        None
+    }
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "trace_capture"))]
+pub fn _handle_capture_command(_cmd_id: u32, _params: &mut Parameters) -> Option<Result<()>> {
+    None
+}
+
+#[cfg(feature = "trace_capture")]
+mod capture {
+    use super::TraceLevel;
+    use crate::{Error, ErrorKind, Parameters, Result};
+    use alloc::collections::VecDeque;
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+    use core::cell::{Cell, UnsafeCell};
+    use core::fmt;
+
+    /// Maximum number of records kept, the oldest ones are dropped first.
+    const CAPACITY: usize = 256;
+
+    struct Record {
+        level: Option<TraceLevel>,
+        location: Option<(String, u32)>,
+        message: String,
+    }
+
+    struct Records {
+        borrowed: Cell<bool>,
+        records: UnsafeCell<Option<VecDeque<Record>>>,
+    }
+
+    // Trusted Applications are single threaded, the buffer is only accessed from the entry points
+    // of the TA instance.
+    unsafe impl Sync for Records {}
+
+    static RECORDS: Records = Records {
+        borrowed: Cell::new(false),
+        records: UnsafeCell::new(None),
+    };
+
+    /// Run `f` with the records, or return `None` if they are already borrowed, e.g. by a message
+    /// printed while the records are updated.
+    fn with_records<R, F: FnOnce(&mut VecDeque<Record>) -> R>(f: F) -> Option<R> {
+        if RECORDS.borrowed.replace(true) {
+            return None;
+        }
+        let records = unsafe { (*RECORDS.records.get()).get_or_insert_with(VecDeque::new) };
+        let result = f(records);
+        RECORDS.borrowed.set(false);
+        Some(result)
+    }
+
+    pub fn push(level: Option<TraceLevel>, location: Option<(&str, u32)>, fmt: fmt::Arguments) {
+        let mut message = fmt.to_string();
+        if message.ends_with('\n') {
+            message.pop();
+        }
+        let record = Record {
+            level,
+            location: location.map(|(file, line)| (file.to_string(), line)),
+            message,
+        };
+        with_records(|records| {
+            if records.len() == CAPACITY {
+                records.pop_front();
+            }
+            records.push_back(record);
+        });
+    }
+
+    // Each record is encoded as the level (0 if none) in one byte, the line as a big-endian
+    // `u32`, then the file and the message, each prefixed with its length as a big-endian `u32`.
+    // An empty file means that the record has no location.
+    pub fn drain(params: &mut Parameters) -> Result<()> {
+        let mut memref = unsafe { params.0.as_memref()? };
+        with_records(|records| {
+            let mut out = Vec::new();
+            for record in records.iter() {
+                let (file, line) = match record.location {
+                    Some((ref file, line)) => (file.as_str(), line),
+                    None => ("", 0),
+                };
+                out.push(record.level.map_or(0, |level| level as u8));
+                out.extend_from_slice(&line.to_be_bytes());
+                out.extend_from_slice(&(file.len() as u32).to_be_bytes());
+                out.extend_from_slice(file.as_bytes());
+                out.extend_from_slice(&(record.message.len() as u32).to_be_bytes());
+                out.extend_from_slice(record.message.as_bytes());
+            }
+            if out.len() > memref.buffer().len() {
+                memref.set_updated_size(out.len());
+                return Err(Error::new(ErrorKind::ShortBuffer));
+            }
+            memref.buffer()[..out.len()].copy_from_slice(&out);
+            memref.set_updated_size(out.len());
+            records.clear();
+            Ok(())
+        })
+        .unwrap_or_else(|| Err(Error::new(ErrorKind::Busy)))
+    }
+}
+
+/// A [log](https://docs.rs/log) backend printing to the trace output with the OP-TEE level
+/// prefixes, for libraries which use the `log` crate.
+///
+/// `Warn` records are printed at the `Info` level and `Trace` records at the `Flow` level, since
+/// OP-TEE has no equivalent levels.
+///
+/// # Example
+///
+/// ```no_run
+/// #[ta_create]
+/// fn create() -> Result<()> {
+///     TraceLogger::init().unwrap();
+///     log::info!("TA created");
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "log")]
+pub struct TraceLogger;
+
+#[cfg(feature = "log")]
+static LOGGER: TraceLogger = TraceLogger;
+
+#[cfg(feature = "log")]
+impl TraceLogger {
+    /// Install the logger, with the maximum level of the `log` crate set from the lower of the
+    /// current runtime trace level and [MAX_LEVEL](MAX_LEVEL).
+    ///
+    /// # Errors
+    ///
+    /// 1) If a logger has already been installed.
+    pub fn init() -> core::result::Result<(), log::SetLoggerError> {
+        log::set_logger(&LOGGER)?;
+        log::set_max_level(match Trace::get_level().min(MAX_LEVEL) {
+            l if l < TraceLevel::Error as i32 => log::LevelFilter::Off,
+            l if l == TraceLevel::Error as i32 => log::LevelFilter::Error,
+            l if l == TraceLevel::Info as i32 => log::LevelFilter::Info,
+            l if l == TraceLevel::Debug as i32 => log::LevelFilter::Debug,
+            _ => log::LevelFilter::Trace,
+        });
+        Ok(())
+    }
+
+    fn trace_level(level: log::Level) -> TraceLevel {
+        match level {
+            log::Level::Error => TraceLevel::Error,
+            log::Level::Warn | log::Level::Info => TraceLevel::Info,
+            log::Level::Debug => TraceLevel::Debug,
+            log::Level::Trace => TraceLevel::Flow,
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+impl log::Log for TraceLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        let level = Self::trace_level(metadata.level());
+        level as i32 <= MAX_LEVEL && level.is_enabled()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let level = Self::trace_level(record.level());
+        match (record.file(), record.line()) {
+            (Some(file), Some(line)) => Trace::_print_level(level, file, line, *record.args()),
+            _ => Trace::_print(format_args!(
+                "{} {}: {}\n",
+                level.prefix(),
+                record.target(),
+                record.args()
+            )),
+        }
+    }
+
+    fn flush(&self) {}
+}
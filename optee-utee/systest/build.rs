@@ -40,8 +40,23 @@ fn main() {
             || field == "keyInformation"
     });
     cfg.skip_type(|s| s == "Memref" || s == "Value");
-    cfg.skip_fn(|s| s == "TEE_BigIntFMMConvertToBigInt");
-    cfg.skip_const(|s| s.starts_with("TA_PROP_STR") || s == "TEE_HANDLE_NULL");
+    // Functions of later GlobalPlatform versions which are missing in the tested OP-TEE headers.
+    cfg.skip_fn(|s| {
+        s == "TEE_BigIntFMMConvertToBigInt"
+            || s == "TEE_IsAlgorithmSupported"
+            || s == "TEE_BigIntSetBit"
+            || s == "TEE_BigIntAssign"
+            || s == "TEE_BigIntAbs"
+            || s == "TEE_BigIntExpMod"
+    });
+    cfg.skip_const(|s| {
+        s.starts_with("TA_PROP_STR")
+            || s == "TEE_HANDLE_NULL"
+            || s == "TEE_MALLOC_NO_FILL"
+            || s == "TEE_MALLOC_NO_SHARE"
+            || s == "TEE_MEMORY_ACCESS_NONSECURE"
+            || s == "TEE_MEMORY_ACCESS_SECURE"
+    });
     cfg.skip_roundtrip(|s| s.starts_with("TEE_") || s.starts_with("utee_") || s == "ta_head");
     cfg.generate("../optee-utee-sys/src/lib.rs", "all.rs");
     println!("cargo:rustc-link-lib=static=mbedtls");
@@ -0,0 +1,8 @@
+fn main() {
+    // `str::as_bytes` and `<[T]>::len` are const since Rust 1.39, older
+    // nightly compilers, e.g. the one of the SDK, need the feature gates.
+    println!("cargo:rustc-check-cfg=cfg(const_str_unstable)");
+    if version_check::is_min_version("1.39.0") == Some(false) {
+        println!("cargo:rustc-cfg=const_str_unstable");
+    }
+}
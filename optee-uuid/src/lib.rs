@@ -0,0 +1,370 @@
+//! The UUID identifying a trusted application, shared by the host and the TA
+//! crates.
+//!
+//! A proto crate exports the UUID as a typed constant, parsed at compile time
+//! with [uuid!](macro.uuid.html):
+//!
+//! ```
+//! use optee_uuid::{uuid, Uuid};
+//!
+//! pub const UUID: Uuid = uuid!("8abcf200-2450-11e4-abe2-0002a5d5c51b");
+//! ```
+//!
+//! The host converts it into an `optee_teec::Uuid`, and the TA into the
+//! `TEE_UUID` of its header, with the `teec` and `utee` features.
+
+#![no_std]
+#![cfg_attr(const_str_unstable, feature(const_str_as_bytes, const_slice_len))]
+
+use core::fmt;
+use core::str::{self, FromStr};
+
+/// Parses a UUID at compile time, a malformed UUID fails the build.
+///
+/// The UUID is written in the hyphenated form, e.g.
+/// `8abcf200-2450-11e4-abe2-0002a5d5c51b`, and can be read from a file with
+/// `include_str!`, as long as the file has no trailing newline.
+///
+/// # Examples
+///
+/// ```
+/// use optee_uuid::{uuid, Uuid};
+///
+/// const UUID: Uuid = uuid!("8abcf200-2450-11e4-abe2-0002a5d5c51b");
+/// assert_eq!(UUID, "8abcf200-2450-11e4-abe2-0002a5d5c51b".parse().unwrap());
+/// ```
+///
+/// A build script can write the UUID into `OUT_DIR`:
+///
+/// ```ignore
+/// pub const UUID: Uuid = uuid!(include_str!(concat!(env!("OUT_DIR"), "/uuid.txt")));
+/// ```
+#[macro_export]
+macro_rules! uuid {
+    ($uuid:expr) => {{
+        const UUID: $crate::Uuid = $crate::Uuid::parse_const($uuid);
+        UUID
+    }};
+}
+
+/// A Universally Unique Identifier (UUID) as defined in RFC4122.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Uuid {
+    bytes: [u8; 16],
+}
+
+/// An error returned when parsing a UUID.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ParseError {
+    /// The string has `len` bytes instead of 32 or 36.
+    InvalidLength(usize),
+    /// The character at `index` is not a hexadecimal digit.
+    InvalidCharacter(char, usize),
+    /// The hyphens are misplaced.
+    InvalidGroups,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::InvalidLength(len) => {
+                write!(f, "invalid length {}, expected 32 or 36 characters", len)
+            }
+            ParseError::InvalidCharacter(c, index) => {
+                write!(f, "invalid character {:?} at index {}", c, index)
+            }
+            ParseError::InvalidGroups => f.write_str("invalid groups, expected 8-4-4-4-12"),
+        }
+    }
+}
+
+/// Returns the value of the hexadecimal digit `s[i]`. An invalid digit is
+/// caught with an out of bounds index, which fails the constant evaluation.
+const fn hex_digit(s: &[u8], i: usize) -> u8 {
+    let c = s[i];
+    let valid = (c.wrapping_sub(b'0') < 10) | ((c | 0x20).wrapping_sub(b'a') < 6);
+    [(c & 0xf) + 9 * (c >> 6)][!valid as usize]
+}
+
+/// Fails the constant evaluation with an out of bounds index if `cond` is
+/// false.
+const fn const_assert(cond: bool) {
+    [()][!cond as usize]
+}
+
+const fn hex_byte(s: &[u8], i: usize) -> u8 {
+    hex_digit(s, i) << 4 | hex_digit(s, i + 1)
+}
+
+/// The position of the bytes in the hyphenated form.
+const POSITIONS: [usize; 16] = [0, 2, 4, 6, 9, 11, 14, 16, 19, 21, 24, 26, 28, 30, 32, 34];
+
+impl Uuid {
+    /// The nil UUID, with all the bits set to zero.
+    pub const NIL: Uuid = Uuid { bytes: [0; 16] };
+
+    /// Creates a UUID from its big-endian bytes.
+    pub const fn from_bytes(bytes: [u8; 16]) -> Uuid {
+        Uuid { bytes }
+    }
+
+    /// Creates a UUID from the fields of the `TEEC_UUID` and `TEE_UUID`
+    /// structures.
+    pub const fn from_fields(
+        time_low: u32,
+        time_mid: u16,
+        time_hi_and_version: u16,
+        clock_seq_and_node: [u8; 8],
+    ) -> Uuid {
+        let n = clock_seq_and_node;
+        Uuid {
+            bytes: [
+                (time_low >> 24) as u8,
+                (time_low >> 16) as u8,
+                (time_low >> 8) as u8,
+                time_low as u8,
+                (time_mid >> 8) as u8,
+                time_mid as u8,
+                (time_hi_and_version >> 8) as u8,
+                time_hi_and_version as u8,
+                n[0],
+                n[1],
+                n[2],
+                n[3],
+                n[4],
+                n[5],
+                n[6],
+                n[7],
+            ],
+        }
+    }
+
+    /// Parses a hyphenated UUID in a constant expression, see
+    /// [uuid!](macro.uuid.html).
+    ///
+    /// # Panics
+    ///
+    /// 1) If the UUID is malformed, which fails the build in a constant
+    ///    expression.
+    pub const fn parse_const(uuid: &str) -> Uuid {
+        let s = uuid.as_bytes();
+        const_assert(s.len() == 36);
+        const_assert((s[8] == b'-') & (s[13] == b'-') & (s[18] == b'-') & (s[23] == b'-'));
+        let p = POSITIONS;
+        Uuid {
+            bytes: [
+                hex_byte(s, p[0]),
+                hex_byte(s, p[1]),
+                hex_byte(s, p[2]),
+                hex_byte(s, p[3]),
+                hex_byte(s, p[4]),
+                hex_byte(s, p[5]),
+                hex_byte(s, p[6]),
+                hex_byte(s, p[7]),
+                hex_byte(s, p[8]),
+                hex_byte(s, p[9]),
+                hex_byte(s, p[10]),
+                hex_byte(s, p[11]),
+                hex_byte(s, p[12]),
+                hex_byte(s, p[13]),
+                hex_byte(s, p[14]),
+                hex_byte(s, p[15]),
+            ],
+        }
+    }
+
+    /// Parses a UUID in the hyphenated form, or in the simple form without
+    /// hyphens.
+    pub fn parse_str(uuid: &str) -> Result<Uuid, ParseError> {
+        let s = uuid.as_bytes();
+        let hyphenated = match s.len() {
+            36 => true,
+            32 => false,
+            len => return Err(ParseError::InvalidLength(len)),
+        };
+        if hyphenated && [8, 13, 18, 23].iter().any(|&i| s[i] != b'-') {
+            return Err(ParseError::InvalidGroups);
+        }
+        let mut bytes = [0u8; 16];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            let pos = if hyphenated { POSITIONS[i] } else { 2 * i };
+            for &index in &[pos, pos + 1] {
+                let digit = match (s[index] as char).to_digit(16) {
+                    Some(digit) => digit as u8,
+                    None => {
+                        // The index may fall inside a multi-byte character.
+                        let (start, c) = uuid
+                            .char_indices()
+                            .take_while(|&(start, _)| start <= index)
+                            .last()
+                            .unwrap();
+                        return Err(ParseError::InvalidCharacter(c, start));
+                    }
+                };
+                *byte = *byte << 4 | digit;
+            }
+        }
+        Ok(Uuid { bytes })
+    }
+
+    /// Returns the big-endian bytes of the UUID.
+    pub const fn as_bytes(&self) -> &[u8; 16] {
+        &self.bytes
+    }
+
+    /// Returns the fields of the `TEEC_UUID` and `TEE_UUID` structures:
+    /// `timeLow`, `timeMid`, `timeHiAndVersion` and `clockSeqAndNode`.
+    pub const fn as_fields(&self) -> (u32, u16, u16, [u8; 8]) {
+        let b = self.bytes;
+        (
+            (b[0] as u32) << 24 | (b[1] as u32) << 16 | (b[2] as u32) << 8 | b[3] as u32,
+            (b[4] as u16) << 8 | b[5] as u16,
+            (b[6] as u16) << 8 | b[7] as u16,
+            [b[8], b[9], b[10], b[11], b[12], b[13], b[14], b[15]],
+        )
+    }
+
+    /// Writes the hyphenated form in lowercase into `buf`.
+    fn encode<'a>(&self, buf: &'a mut [u8; 36]) -> &'a str {
+        const DIGITS: &[u8; 16] = b"0123456789abcdef";
+        let mut pos = 0;
+        for (i, byte) in self.bytes.iter().enumerate() {
+            if i == 4 || i == 6 || i == 8 || i == 10 {
+                buf[pos] = b'-';
+                pos += 1;
+            }
+            buf[pos] = DIGITS[(byte >> 4) as usize];
+            buf[pos + 1] = DIGITS[(byte & 0xf) as usize];
+            pos += 2;
+        }
+        str::from_utf8(buf).unwrap()
+    }
+}
+
+impl fmt::Display for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.encode(&mut [0; 36]))
+    }
+}
+
+impl FromStr for Uuid {
+    type Err = ParseError;
+
+    fn from_str(uuid: &str) -> Result<Uuid, ParseError> {
+        Uuid::parse_str(uuid)
+    }
+}
+
+#[cfg(feature = "teec")]
+impl Uuid {
+    /// Converts the UUID into the `TEEC_UUID` of the TEE client API.
+    pub const fn to_teec_uuid(&self) -> optee_teec_sys::TEEC_UUID {
+        let (time_low, time_mid, time_hi_and_version, clock_seq_and_node) = self.as_fields();
+        optee_teec_sys::TEEC_UUID {
+            timeLow: time_low,
+            timeMid: time_mid,
+            timeHiAndVersion: time_hi_and_version,
+            clockSeqAndNode: clock_seq_and_node,
+        }
+    }
+}
+
+#[cfg(feature = "teec")]
+impl From<Uuid> for optee_teec_sys::TEEC_UUID {
+    fn from(uuid: Uuid) -> Self {
+        uuid.to_teec_uuid()
+    }
+}
+
+#[cfg(feature = "teec")]
+impl From<optee_teec_sys::TEEC_UUID> for Uuid {
+    fn from(raw: optee_teec_sys::TEEC_UUID) -> Self {
+        Uuid::from_fields(
+            raw.timeLow,
+            raw.timeMid,
+            raw.timeHiAndVersion,
+            raw.clockSeqAndNode,
+        )
+    }
+}
+
+#[cfg(feature = "utee")]
+impl Uuid {
+    /// Converts the UUID into the `TEE_UUID` of the TEE internal core API,
+    /// e.g. for the header of a TA.
+    pub const fn to_tee_uuid(&self) -> optee_utee_sys::TEE_UUID {
+        let (time_low, time_mid, time_hi_and_version, clock_seq_and_node) = self.as_fields();
+        optee_utee_sys::TEE_UUID {
+            timeLow: time_low,
+            timeMid: time_mid,
+            timeHiAndVersion: time_hi_and_version,
+            clockSeqAndNode: clock_seq_and_node,
+        }
+    }
+}
+
+#[cfg(feature = "utee")]
+impl From<Uuid> for optee_utee_sys::TEE_UUID {
+    fn from(uuid: Uuid) -> Self {
+        uuid.to_tee_uuid()
+    }
+}
+
+#[cfg(feature = "utee")]
+impl From<optee_utee_sys::TEE_UUID> for Uuid {
+    fn from(raw: optee_utee_sys::TEE_UUID) -> Self {
+        Uuid::from_fields(
+            raw.timeLow,
+            raw.timeMid,
+            raw.timeHiAndVersion,
+            raw.clockSeqAndNode,
+        )
+    }
+}
+
+/// Serialized as a hyphenated string in human readable formats, e.g. JSON,
+/// and as 16 bytes otherwise.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Uuid {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(self.encode(&mut [0; 36]))
+        } else {
+            serializer.serialize_bytes(&self.bytes)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Uuid {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Uuid, D::Error> {
+        struct UuidVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for UuidVisitor {
+            type Value = Uuid;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a UUID string or 16 bytes")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Uuid, E> {
+                Uuid::parse_str(v).map_err(E::custom)
+            }
+
+            fn visit_bytes<E: serde::de::Error>(self, v: &[u8]) -> Result<Uuid, E> {
+                if v.len() != 16 {
+                    return Err(E::invalid_length(v.len(), &self));
+                }
+                let mut bytes = [0u8; 16];
+                bytes.copy_from_slice(v);
+                Ok(Uuid::from_bytes(bytes))
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(UuidVisitor)
+        } else {
+            deserializer.deserialize_bytes(UuidVisitor)
+        }
+    }
+}
@@ -24,14 +24,22 @@ screen -S qemu_screen -p 0 -X stuff "^C"
 sleep 5
 
 {
-	grep -q "\[.*] > \[.*]\|\[.*] < \[.*]\|\[.*] == \[.*]" /tmp/serial.log &&
-	grep -q "\[.*] in u8 array is \[.*]" /tmp/serial.log &&
-	grep -q "\[.*] in i32 is [0-9]*" /tmp/serial.log &&
-	grep -q "\[.*] + \[.*] = \[.*]" /tmp/serial.log &&
-	grep -q "\[.*] - \[.*] = \[.*]" /tmp/serial.log &&
-	grep -q "\[.*] \* \[.*] = \[.*]" /tmp/serial.log &&
-	grep -q "\[.*] / \[.*] = \[.*]" /tmp/serial.log &&
-	grep -q "\[.*] % \[.*] = \[.*]" /tmp/serial.log &&
+	grep -q "81985529216486895 > 2\." /tmp/serial.log &&
+	grep -q "81985529216486895 in u8 array is \[.*]" /tmp/serial.log &&
+	grep -q "2 in i32 is 2\." /tmp/serial.log &&
+	grep -q "81985529216486895 in hex is 0x123456789abcdef\." /tmp/serial.log &&
+	grep -q "81985529216486895 + 2 = 81985529216486897\." /tmp/serial.log &&
+	grep -q "81985529216486895 - 2 = 81985529216486893\." /tmp/serial.log &&
+	grep -q "81985529216486895 \* 2 = 163971058432973790\." /tmp/serial.log &&
+	grep -q "81985529216486895 / 2 = 40992764608243447, ramians 1\." /tmp/serial.log &&
+	grep -q "81985529216486895 % 2 = 1\." /tmp/serial.log &&
+	grep -q "2 round trips through FMM mod 81985529216486895 as 2\." /tmp/serial.log &&
+	grep -q "81985529216486893 round trips through FMM mod 81985529216486895 as 81985529216486893\." /tmp/serial.log &&
+	grep -q "2 \* 2 mod 81985529216486895 = 4\." /tmp/serial.log &&
+	grep -q "81985529216486893 ^ 2 mod 81985529216486895 = 4\." /tmp/serial.log &&
+	grep -q "pow: 2 ^ 100 mod 81985529216486895 = 16561393893376\." /tmp/serial.log &&
+	grep -q "exp_mod: 2 ^ 100 mod 81985529216486895 = 16561393893376\." /tmp/serial.log &&
+	grep -q "TA heap: current [0-9]* bytes, peak [1-9][0-9]* bytes, 0 failed allocations" /tmp/serial.log &&
 	grep -q "Success" screenlog.0
 } || {
 	cat -v screenlog.0
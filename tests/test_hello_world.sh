@@ -24,9 +24,16 @@ screen -S qemu_screen -p 0 -X stuff "^C"
 sleep 5
 
 {
+	grep -q "TEE is available" screenlog.0 &&
 	grep -q "original value is 29" screenlog.0 &&
 	grep -q "inc value is 129" screenlog.0 &&
 	grep -q "dec value is 29" screenlog.0 &&
+	grep -q "dec value is rejected: ValueUnderflow" screenlog.0 &&
+	grep -q "DBG src/main.rs:[0-9]*: value incremented to 129" /tmp/serial.log &&
+	grep -q "DBG src/main.rs:[0-9]*: value decremented to 29" /tmp/serial.log &&
+	grep -q "TA commands invoked by this instance: 3" /tmp/serial.log &&
+	grep -q "TA Debug src/main.rs:[0-9]*: value incremented to 129" screenlog.0 &&
+	grep -q "TA Debug src/main.rs:[0-9]*: value decremented to 29" screenlog.0 &&
 	grep -q "Success" screenlog.0
 } || {
 	cat -v screenlog.0
@@ -26,6 +26,7 @@ sleep 5
 {
 	grep -q "Invoking TA to generate random UUID" screenlog.0 &&
 	grep -q "Generate random UUID: [a-z0-9]*-[a-z0-9]*-[a-z0-9]*-[a-z0-9]*" screenlog.0 &&
+	grep -q "TA rolled a dice: [1-6]" /tmp/serial.log &&
 	grep -q "Success" screenlog.0
 } || {
         cat -v screenlog.0
@@ -27,6 +27,7 @@ sleep 5
 	grep -q "Success" screenlog.0 &&
 	grep -q "\[+] Get REE time (second: [0-9]*, millisecond: [0-9]*)" /tmp/serial.log &&
 	grep -q "\[+] Now wait 1 second in TEE" /tmp/serial.log &&
+	grep -q "\[+] Waited [0-9]* ms." /tmp/serial.log &&
 	grep -q "\[+] Get system time (second: [0-9]*, millisecond: [0-9]*)" /tmp/serial.log &&
 	grep -q "\[+] After set the TA time 5 seconds ahead of system time, new TA time (second: [0-9]*, millisecond: [0-9]*)" /tmp/serial.log
 } || {